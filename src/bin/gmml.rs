@@ -1,16 +1,126 @@
-use gmml::parse;
+use gmml::parse::Scanner;
+use gmml::{Diagnostic, Document, Query};
 use std::env;
 use std::fs;
+use std::io::{self, IsTerminal, Read};
+use std::process;
+
+fn read_source(argument: &str) -> io::Result<String> {
+    if argument == "-" {
+        let mut src = String::new();
+        io::stdin().lock().read_to_string(&mut src)?;
+        Ok(src)
+    } else {
+        fs::read_to_string(argument)
+    }
+}
+
+fn use_color() -> bool {
+    env::var_os("NO_COLOR").is_none() && io::stderr().is_terminal()
+}
+
+fn load(argument: &str) -> Document {
+    let src = match read_source(argument) {
+        Ok(src) => src,
+        Err(err) => {
+            eprintln!("error: {}: {}", argument, err);
+            process::exit(1);
+        }
+    };
+    let filename = if argument == "-" { "<stdin>" } else { argument };
+    let scanner = Scanner::from_str(&src).with_filename(filename);
+    let (result, errors) = gmml::parse_scanner_recovering(scanner);
+    if !errors.is_empty() {
+        for err in &errors {
+            eprintln!("{}", Diagnostic::from(err).render(&src, use_color()));
+        }
+        eprintln!("error: aborting due to {} previous error(s)", errors.len());
+        process::exit(1);
+    }
+    result
+}
+
+fn query(args: &[String]) {
+    let (file, path) = match args {
+        [file, path] => (file, path),
+        _ => {
+            eprintln!("usage: gmml query FILE QUERY");
+            process::exit(2);
+        }
+    };
+    let query = match Query::parse(path) {
+        Ok(query) => query,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            process::exit(2);
+        }
+    };
+    let document = load(file);
+    for value in query.select(&document) {
+        println!("{}", value);
+    }
+}
+
+/// Rewrites each file in the canonical style, or with `--check` lists the
+/// files that are not formatted and fails if there are any.
+fn format(args: &[String]) {
+    let check = args.iter().any(|arg| arg == "--check");
+    let files: Vec<&String> = args.iter().filter(|arg| *arg != "--check").collect();
+    if files.is_empty() {
+        eprintln!("usage: gmml fmt [--check] FILE...");
+        process::exit(2);
+    }
+    let mut failed = false;
+    for argument in files {
+        let src = match read_source(argument) {
+            Ok(src) => src,
+            Err(err) => {
+                eprintln!("error: {}: {}", argument, err);
+                process::exit(1);
+            }
+        };
+        let filename = if argument == "-" { "<stdin>" } else { argument };
+        let formatted = match gmml::format::format_str(&src) {
+            Ok(formatted) => formatted,
+            Err(err) => {
+                let diagnostic = Diagnostic::from(&err).with_file(filename);
+                eprintln!("{}", diagnostic.render(&src, use_color()));
+                failed = true;
+                continue;
+            }
+        };
+        if formatted == src {
+            if argument == "-" && !check {
+                print!("{}", formatted);
+            }
+        } else if check {
+            println!("{} is not formatted", filename);
+            failed = true;
+        } else if argument == "-" {
+            print!("{}", formatted);
+        } else if let Err(err) = fs::write(argument, formatted) {
+            eprintln!("error: {}: {}", argument, err);
+            failed = true;
+        }
+    }
+    if failed {
+        process::exit(1);
+    }
+}
 
 fn main() {
-    let mut iter = env::args();
-    iter.next();
-    for argument in iter {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("query") => return query(&args[1..]),
+        Some("fmt") => return format(&args[1..]),
+        _ => {}
+    }
+    for argument in &args {
         println!("{}", argument);
-        let scanner = parse::Scanner::new(argument.to_string());
-        let sym: Vec<parse::Token> = scanner.into_iter().collect();
-        let mut parser = parse::Parser::new(sym);
-        let result = parser.parse().expect("failed to parse");
-        println!("{:#?}\n", result);
+        let result = load(argument);
+        for block in &result {
+            println!("[{}]\n{:#?}", block.name, block.to_value());
+        }
+        println!();
     }
 }
@@ -0,0 +1,955 @@
+//! Lossless concrete syntax tree.
+//!
+//! The tree keeps every byte of the source, including whitespace, comments
+//! and malformed input, so `Document::parse(src).to_string() == src` always
+//! holds. It is split in two layers: immutable green nodes, which only know
+//! their kind, text and children, and red `SyntaxNode`s, which add parent
+//! links and absolute offsets on top. Typed wrappers such as `Block` and
+//! `Entry` give access to the parts of each construct.
+
+use crate::parse::{Scanner, Symbol, Token};
+use crate::value::EdgeKind;
+use std::fmt;
+use std::ops::Range;
+use std::rc::Rc;
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Hash)]
+pub enum SyntaxKind {
+    // Tokens
+    Identifier,
+    Integer,
+    Float,
+    String,
+    Bool,
+    Null,
+    LeftBracket,
+    RightBracket,
+    LeftParen,
+    RightParen,
+    LeftBrace,
+    RightBrace,
+    Comma,
+    Colon,
+    Equal,
+    Arrow,
+    Plus,
+    Punct,
+    Whitespace,
+    Comment,
+    Newline,
+    ErrorToken,
+    // Nodes
+    Root,
+    Block,
+    Header,
+    Entry,
+    /// The id `e1:` of a named edge.
+    EdgeId,
+    Edge,
+    Key,
+    /// A group of keys `(A, B)` at one end of an edge.
+    Group,
+    /// The nodes `X + Y` on one side of a hyperedge.
+    Join,
+    Literal,
+    List,
+    Struct,
+    Pair,
+    Message,
+    Args,
+    Error,
+}
+
+impl SyntaxKind {
+    /// Whitespace, comments and newlines.
+    pub fn is_trivia(self) -> bool {
+        matches!(
+            self,
+            SyntaxKind::Whitespace | SyntaxKind::Comment | SyntaxKind::Newline
+        )
+    }
+    fn starts_key(self) -> bool {
+        matches!(
+            self,
+            SyntaxKind::Identifier
+                | SyntaxKind::Integer
+                | SyntaxKind::Float
+                | SyntaxKind::String
+                | SyntaxKind::Bool
+                | SyntaxKind::Null
+        )
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct GreenToken {
+    kind: SyntaxKind,
+    text: String,
+}
+
+impl GreenToken {
+    pub fn new(kind: SyntaxKind, text: &str) -> Self {
+        Self {
+            kind,
+            text: text.to_string(),
+        }
+    }
+    pub fn kind(&self) -> SyntaxKind {
+        self.kind
+    }
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum GreenElement {
+    Node(Rc<GreenNode>),
+    Token(Rc<GreenToken>),
+}
+
+impl GreenElement {
+    pub fn kind(&self) -> SyntaxKind {
+        match self {
+            GreenElement::Node(node) => node.kind,
+            GreenElement::Token(token) => token.kind,
+        }
+    }
+    pub fn len(&self) -> usize {
+        match self {
+            GreenElement::Node(node) => node.len,
+            GreenElement::Token(token) => token.text.len(),
+        }
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl fmt::Display for GreenElement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GreenElement::Node(node) => write!(f, "{}", node),
+            GreenElement::Token(token) => write!(f, "{}", token.text),
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct GreenNode {
+    kind: SyntaxKind,
+    len: usize,
+    children: Vec<GreenElement>,
+}
+
+impl GreenNode {
+    pub fn new(kind: SyntaxKind, children: Vec<GreenElement>) -> Self {
+        Self {
+            kind,
+            len: children.iter().map(GreenElement::len).sum(),
+            children,
+        }
+    }
+    pub fn kind(&self) -> SyntaxKind {
+        self.kind
+    }
+    /// The length of the node's text in bytes.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    pub fn children(&self) -> &[GreenElement] {
+        &self.children
+    }
+}
+
+impl fmt::Display for GreenNode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for child in &self.children {
+            write!(f, "{}", child)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct NodeData {
+    green: Rc<GreenNode>,
+    parent: Option<SyntaxNode>,
+    offset: usize,
+}
+
+/// A node of the red tree: a green node with its parent and position.
+#[derive(Debug, Clone)]
+pub struct SyntaxNode(Rc<NodeData>);
+
+/// A token of the red tree.
+#[derive(Debug, Clone)]
+pub struct SyntaxToken {
+    green: Rc<GreenToken>,
+    parent: SyntaxNode,
+    offset: usize,
+}
+
+#[derive(Debug, Clone)]
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(SyntaxToken),
+}
+
+impl SyntaxElement {
+    pub fn kind(&self) -> SyntaxKind {
+        match self {
+            SyntaxElement::Node(node) => node.kind(),
+            SyntaxElement::Token(token) => token.kind(),
+        }
+    }
+    pub fn text_range(&self) -> Range<usize> {
+        match self {
+            SyntaxElement::Node(node) => node.text_range(),
+            SyntaxElement::Token(token) => token.text_range(),
+        }
+    }
+}
+
+impl SyntaxNode {
+    pub fn new_root(green: Rc<GreenNode>) -> Self {
+        SyntaxNode(Rc::new(NodeData {
+            green,
+            parent: None,
+            offset: 0,
+        }))
+    }
+    pub fn kind(&self) -> SyntaxKind {
+        self.0.green.kind
+    }
+    pub fn green(&self) -> &Rc<GreenNode> {
+        &self.0.green
+    }
+    pub fn parent(&self) -> Option<&SyntaxNode> {
+        self.0.parent.as_ref()
+    }
+    /// The byte range of the node in the source.
+    pub fn text_range(&self) -> Range<usize> {
+        self.0.offset..self.0.offset + self.0.green.len
+    }
+    pub fn text(&self) -> String {
+        self.0.green.to_string()
+    }
+    pub fn children_with_tokens(&self) -> impl Iterator<Item = SyntaxElement> + '_ {
+        let mut offset = self.0.offset;
+        self.0.green.children.iter().map(move |child| {
+            let start = offset;
+            offset += child.len();
+            match child {
+                GreenElement::Node(green) => SyntaxElement::Node(SyntaxNode(Rc::new(NodeData {
+                    green: green.clone(),
+                    parent: Some(self.clone()),
+                    offset: start,
+                }))),
+                GreenElement::Token(green) => SyntaxElement::Token(SyntaxToken {
+                    green: green.clone(),
+                    parent: self.clone(),
+                    offset: start,
+                }),
+            }
+        })
+    }
+    pub fn children(&self) -> impl Iterator<Item = SyntaxNode> + '_ {
+        self.children_with_tokens().filter_map(|child| match child {
+            SyntaxElement::Node(node) => Some(node),
+            SyntaxElement::Token(_) => None,
+        })
+    }
+    pub fn tokens(&self) -> impl Iterator<Item = SyntaxToken> + '_ {
+        self.children_with_tokens().filter_map(|child| match child {
+            SyntaxElement::Token(token) => Some(token),
+            SyntaxElement::Node(_) => None,
+        })
+    }
+    /// Every token below the node, in source order.
+    pub fn descendant_tokens(&self) -> Vec<SyntaxToken> {
+        let mut tokens = Vec::new();
+        for child in self.children_with_tokens() {
+            match child {
+                SyntaxElement::Node(node) => tokens.extend(node.descendant_tokens()),
+                SyntaxElement::Token(token) => tokens.push(token),
+            }
+        }
+        tokens
+    }
+    fn child(&self, kind: SyntaxKind) -> Option<SyntaxNode> {
+        self.children().find(|child| child.kind() == kind)
+    }
+    fn token(&self, kind: SyntaxKind) -> Option<SyntaxToken> {
+        self.tokens().find(|token| token.kind() == kind)
+    }
+}
+
+impl PartialEq for SyntaxNode {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0.green, &other.0.green) && self.0.offset == other.0.offset
+    }
+}
+
+impl fmt::Display for SyntaxNode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0.green)
+    }
+}
+
+impl SyntaxToken {
+    pub fn kind(&self) -> SyntaxKind {
+        self.green.kind
+    }
+    pub fn text(&self) -> &str {
+        &self.green.text
+    }
+    pub fn parent(&self) -> &SyntaxNode {
+        &self.parent
+    }
+    pub fn text_range(&self) -> Range<usize> {
+        self.offset..self.offset + self.green.text.len()
+    }
+}
+
+impl fmt::Display for SyntaxToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.green.text)
+    }
+}
+
+fn token_kind(tok: &Token, text: &str) -> SyntaxKind {
+    match tok {
+        Token::Identifier(_) => SyntaxKind::Identifier,
+        Token::Integer(_) => SyntaxKind::Integer,
+        Token::Float(_) => SyntaxKind::Float,
+        Token::String(_) => SyntaxKind::String,
+        Token::Bool(_) => SyntaxKind::Bool,
+        Token::Null => SyntaxKind::Null,
+        Token::Symbol(Symbol::LeftBracket) => SyntaxKind::LeftBracket,
+        Token::Symbol(Symbol::RightBracket) => SyntaxKind::RightBracket,
+        Token::Symbol(Symbol::LeftParen) => SyntaxKind::LeftParen,
+        Token::Symbol(Symbol::RightParen) => SyntaxKind::RightParen,
+        Token::Symbol(Symbol::LeftBrace) => SyntaxKind::LeftBrace,
+        Token::Symbol(Symbol::RightBrace) => SyntaxKind::RightBrace,
+        Token::Symbol(Symbol::Comma) => SyntaxKind::Comma,
+        Token::Symbol(Symbol::Colon) => SyntaxKind::Colon,
+        Token::Symbol(Symbol::Equal) => SyntaxKind::Equal,
+        Token::Symbol(Symbol::Plus) => SyntaxKind::Plus,
+        Token::Symbol(_) => SyntaxKind::Punct,
+        Token::Arrow(_) => SyntaxKind::Arrow,
+        Token::Newline => SyntaxKind::Newline,
+        Token::Whitespace if text.starts_with(';') => SyntaxKind::Comment,
+        Token::Whitespace => SyntaxKind::Whitespace,
+        Token::Error => SyntaxKind::ErrorToken,
+    }
+}
+
+/// Splits `src` into tokens covering every byte; text the scanner could not
+/// make sense of becomes `ErrorToken`s.
+fn lex(src: &str) -> Vec<(SyntaxKind, &str)> {
+    let (toks, _) = Scanner::from_str(src).tokenize_recovering();
+    let mut out = Vec::new();
+    let mut offset = 0;
+    for tok in toks {
+        let (start, end) = (tok.span.start, tok.span.end);
+        if start < offset || end > src.len() {
+            continue;
+        }
+        if start > offset {
+            out.push((SyntaxKind::ErrorToken, &src[offset..start]));
+        }
+        if end > start {
+            let text = &src[start..end];
+            out.push((token_kind(&tok.node, text), text));
+        }
+        offset = end;
+    }
+    if offset < src.len() {
+        out.push((SyntaxKind::ErrorToken, &src[offset..]));
+    }
+    out
+}
+
+struct Builder<'a> {
+    toks: Vec<(SyntaxKind, &'a str)>,
+    pos: usize,
+    stack: Vec<(SyntaxKind, Vec<GreenElement>)>,
+}
+
+impl<'a> Builder<'a> {
+    fn current(&self) -> Option<SyntaxKind> {
+        self.toks.get(self.pos).map(|(kind, _)| *kind)
+    }
+    /// The kind of the next token that is not trivia.
+    fn lookahead(&self) -> Option<SyntaxKind> {
+        self.toks[self.pos..]
+            .iter()
+            .map(|(kind, _)| *kind)
+            .find(|kind| !kind.is_trivia())
+    }
+    fn start(&mut self, kind: SyntaxKind) {
+        self.stack.push((kind, Vec::new()));
+    }
+    fn checkpoint(&self) -> usize {
+        self.stack.last().map_or(0, |(_, children)| children.len())
+    }
+    /// Starts a node containing everything added since `checkpoint`.
+    fn start_at(&mut self, checkpoint: usize, kind: SyntaxKind) {
+        let children = match self.stack.last_mut() {
+            Some((_, children)) => children.split_off(checkpoint),
+            None => Vec::new(),
+        };
+        self.stack.push((kind, children));
+    }
+    fn finish(&mut self) {
+        let (kind, children) = self.stack.pop().unwrap();
+        let node = GreenElement::Node(Rc::new(GreenNode::new(kind, children)));
+        self.stack.last_mut().unwrap().1.push(node);
+    }
+    fn bump(&mut self) {
+        let (kind, text) = self.toks[self.pos];
+        self.pos += 1;
+        let token = GreenElement::Token(Rc::new(GreenToken::new(kind, text)));
+        self.stack.last_mut().unwrap().1.push(token);
+    }
+    fn eat(&mut self, kind: SyntaxKind) -> bool {
+        if self.current() == Some(kind) {
+            self.bump();
+            true
+        } else {
+            false
+        }
+    }
+    /// Adds whitespace and comments, but not newlines.
+    fn trivia(&mut self) {
+        while let Some(SyntaxKind::Whitespace) | Some(SyntaxKind::Comment) = self.current() {
+            self.bump();
+        }
+    }
+    fn blank(&mut self) {
+        while self.current().is_some_and(SyntaxKind::is_trivia) {
+            self.bump();
+        }
+    }
+    fn error_line(&mut self) {
+        self.start(SyntaxKind::Error);
+        while !matches!(self.current(), None | Some(SyntaxKind::Newline)) {
+            self.bump();
+        }
+        self.finish();
+    }
+
+    fn root(&mut self) -> GreenNode {
+        self.start(SyntaxKind::Root);
+        loop {
+            self.blank();
+            match self.current() {
+                None => break,
+                Some(SyntaxKind::LeftBracket) => self.block(),
+                Some(_) => self.error_line(),
+            }
+        }
+        let (kind, children) = self.stack.pop().unwrap();
+        GreenNode::new(kind, children)
+    }
+    fn block(&mut self) {
+        self.start(SyntaxKind::Block);
+        self.start(SyntaxKind::Header);
+        self.bump();
+        self.trivia();
+        if self.eat(SyntaxKind::Identifier) {
+            self.path();
+        }
+        self.trivia();
+        self.eat(SyntaxKind::RightBracket);
+        self.finish();
+        loop {
+            self.blank();
+            match self.current() {
+                None | Some(SyntaxKind::LeftBracket) => break,
+                Some(kind) if kind.starts_key() || kind == SyntaxKind::LeftParen => self.entry(),
+                Some(_) => self.error_line(),
+            }
+        }
+        self.finish();
+    }
+    fn entry(&mut self) {
+        self.start(SyntaxKind::Entry);
+        if self.current() == Some(SyntaxKind::Identifier)
+            && self.after_key() == Some(SyntaxKind::Colon)
+        {
+            self.start(SyntaxKind::EdgeId);
+            self.key();
+            self.trivia();
+            self.bump();
+            self.finish();
+            self.trivia();
+            if !self
+                .current()
+                .is_some_and(|kind| kind.starts_key() || kind == SyntaxKind::LeftParen)
+            {
+                // a half-typed `e1:` with no edge after it
+                self.start(SyntaxKind::Error);
+                self.finish();
+                self.finish();
+                return;
+            }
+        }
+        let checkpoint = self.checkpoint();
+        self.endpoint();
+        if self.lookahead() == Some(SyntaxKind::Arrow) {
+            // a chain `A -> B -> C` is a single edge node with every key
+            self.start_at(checkpoint, SyntaxKind::Edge);
+            while self.lookahead() == Some(SyntaxKind::Arrow) {
+                self.blank();
+                self.bump();
+                self.blank();
+                match self.current() {
+                    Some(kind) if kind.starts_key() || kind == SyntaxKind::LeftParen => {
+                        self.endpoint()
+                    }
+                    _ => break,
+                }
+            }
+            self.finish();
+        }
+        self.trivia();
+        if self.eat(SyntaxKind::Equal) || self.eat(SyntaxKind::Colon) {
+            self.blank();
+            self.value();
+        }
+        if !matches!(self.lookahead_on_line(), None | Some(SyntaxKind::Newline)) {
+            self.trivia();
+            self.error_line();
+        }
+        self.finish();
+    }
+    /// The token after the current one, skipping whitespace and comments.
+    fn after_key(&self) -> Option<SyntaxKind> {
+        self.toks[self.pos + 1..]
+            .iter()
+            .map(|(kind, _)| *kind)
+            .find(|kind| !matches!(kind, SyntaxKind::Whitespace | SyntaxKind::Comment))
+    }
+    /// The next token on this line that is not whitespace or a comment.
+    fn lookahead_on_line(&self) -> Option<SyntaxKind> {
+        self.toks[self.pos..]
+            .iter()
+            .map(|(kind, _)| *kind)
+            .find(|kind| !matches!(kind, SyntaxKind::Whitespace | SyntaxKind::Comment))
+    }
+    fn key(&mut self) {
+        self.start(SyntaxKind::Key);
+        self.bump();
+        self.path();
+        self.finish();
+    }
+    /// Adds the rest of a dotted path `Router.eth0` after its first name.
+    fn path(&mut self) {
+        while self.toks[self.pos - 1].0 == SyntaxKind::Identifier
+            && self.toks.get(self.pos) == Some(&(SyntaxKind::Punct, "."))
+            && self.toks.get(self.pos + 1).map(|(kind, _)| *kind) == Some(SyntaxKind::Identifier)
+        {
+            self.bump();
+            self.bump();
+        }
+    }
+    /// A key, a group of keys `(A, B)` or the joined keys `X + Y`.
+    fn endpoint(&mut self) {
+        if self.current() != Some(SyntaxKind::LeftParen) {
+            let checkpoint = self.checkpoint();
+            self.key();
+            if self.lookahead_on_line() == Some(SyntaxKind::Plus) {
+                self.start_at(checkpoint, SyntaxKind::Join);
+                while self.lookahead_on_line() == Some(SyntaxKind::Plus) {
+                    self.trivia();
+                    self.bump();
+                    self.blank();
+                    if !self.current().is_some_and(SyntaxKind::starts_key) {
+                        break;
+                    }
+                    self.key();
+                }
+                self.finish();
+            }
+            return;
+        }
+        self.start(SyntaxKind::Group);
+        self.bump();
+        loop {
+            self.blank();
+            match self.current() {
+                None => break,
+                Some(SyntaxKind::RightParen) => {
+                    self.bump();
+                    break;
+                }
+                Some(SyntaxKind::Comma) => self.bump(),
+                Some(SyntaxKind::LeftBracket) if self.at_header() => break,
+                Some(kind) if kind.starts_key() => self.key(),
+                Some(_) => self.value(),
+            }
+        }
+        self.finish();
+    }
+    fn value(&mut self) {
+        match self.current() {
+            Some(SyntaxKind::LeftBrace) => self.structure(),
+            Some(SyntaxKind::LeftParen) => self.list(SyntaxKind::List),
+            Some(SyntaxKind::Identifier)
+                if self.toks.get(self.pos + 1).map(|(kind, _)| *kind)
+                    == Some(SyntaxKind::LeftParen) =>
+            {
+                self.start(SyntaxKind::Message);
+                self.bump();
+                self.list(SyntaxKind::Args);
+                self.finish();
+            }
+            Some(kind) if kind.starts_key() => {
+                self.start(SyntaxKind::Literal);
+                self.bump();
+                self.path();
+                self.finish();
+            }
+            Some(SyntaxKind::Newline) | None => {
+                self.start(SyntaxKind::Error);
+                self.finish();
+            }
+            Some(_) => {
+                self.start(SyntaxKind::Error);
+                self.bump();
+                self.finish();
+            }
+        }
+    }
+    fn list(&mut self, kind: SyntaxKind) {
+        self.start(kind);
+        self.bump();
+        loop {
+            self.blank();
+            match self.current() {
+                None => break,
+                Some(SyntaxKind::RightParen) => {
+                    self.bump();
+                    break;
+                }
+                Some(SyntaxKind::Comma) => self.bump(),
+                Some(SyntaxKind::LeftBracket) if self.at_header() => break,
+                Some(_) => self.value(),
+            }
+        }
+        self.finish();
+    }
+    fn structure(&mut self) {
+        self.start(SyntaxKind::Struct);
+        self.bump();
+        loop {
+            self.blank();
+            match self.current() {
+                None => break,
+                Some(SyntaxKind::RightBrace) => {
+                    self.bump();
+                    break;
+                }
+                Some(SyntaxKind::Comma) => self.bump(),
+                Some(SyntaxKind::LeftBracket) if self.at_header() => break,
+                Some(kind) if kind.starts_key() => {
+                    self.start(SyntaxKind::Pair);
+                    self.key();
+                    self.blank();
+                    if self.eat(SyntaxKind::Colon) {
+                        self.blank();
+                        self.value();
+                    }
+                    self.finish();
+                }
+                Some(_) => {
+                    self.start(SyntaxKind::Error);
+                    self.bump();
+                    self.finish();
+                }
+            }
+        }
+        self.finish();
+    }
+    /// Whether the current `[` starts a line, so an unclosed group does not
+    /// swallow the following blocks.
+    fn at_header(&self) -> bool {
+        self.toks[..self.pos]
+            .iter()
+            .rev()
+            .find(|(kind, _)| *kind != SyntaxKind::Whitespace)
+            .is_none_or(|(kind, _)| *kind == SyntaxKind::Newline)
+    }
+}
+
+/// Parses `src` into a lossless tree. This never fails: malformed input
+/// ends up in `Error` nodes and `ErrorToken`s.
+pub fn parse(src: &str) -> Document {
+    let mut builder = Builder {
+        toks: lex(src),
+        pos: 0,
+        stack: Vec::new(),
+    };
+    let root = builder.root();
+    Document(SyntaxNode::new_root(Rc::new(root)))
+}
+
+/// The root of a parsed file.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Document(SyntaxNode);
+
+impl Document {
+    pub fn parse(src: &str) -> Self {
+        parse(src)
+    }
+    pub fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub fn blocks(&self) -> impl Iterator<Item = Block> + '_ {
+        self.0.children().filter_map(Block::cast)
+    }
+    /// The first block named `name`.
+    pub fn block(&self, name: &str) -> Option<Block> {
+        self.blocks()
+            .find(|block| block.name().as_deref() == Some(name))
+    }
+    /// Whether the tree contains any `Error` node or `ErrorToken`.
+    pub fn has_errors(&self) -> bool {
+        fn walk(node: &GreenNode) -> bool {
+            node.kind == SyntaxKind::Error
+                || node.children.iter().any(|child| match child {
+                    GreenElement::Node(node) => walk(node),
+                    GreenElement::Token(token) => token.kind == SyntaxKind::ErrorToken,
+                })
+        }
+        walk(self.0.green())
+    }
+}
+
+impl fmt::Display for Document {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Strips the backticks of a quoted identifier.
+fn identifier_text(token: &SyntaxToken) -> String {
+    let text = token.text();
+    match text.strip_prefix('`') {
+        Some(text) => text.trim_end_matches('`').to_string(),
+        None => text.to_string(),
+    }
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct Block(SyntaxNode);
+
+impl Block {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            SyntaxKind::Block => Some(Block(node)),
+            _ => None,
+        }
+    }
+    pub fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub fn header(&self) -> Option<SyntaxNode> {
+        self.0.child(SyntaxKind::Header)
+    }
+    pub fn name_token(&self) -> Option<SyntaxToken> {
+        self.header()?.token(SyntaxKind::Identifier)
+    }
+    /// The names of the header, several for a nested block `[Model.Attack]`.
+    pub fn name_tokens(&self) -> Vec<SyntaxToken> {
+        self.header().map_or(Vec::new(), |header| {
+            header
+                .tokens()
+                .filter(|token| token.kind() == SyntaxKind::Identifier)
+                .collect()
+        })
+    }
+    pub fn name(&self) -> Option<String> {
+        let names: Vec<String> = self.name_tokens().iter().map(identifier_text).collect();
+        if names.is_empty() {
+            None
+        } else {
+            Some(names.join("."))
+        }
+    }
+    pub fn entries(&self) -> impl Iterator<Item = Entry> + '_ {
+        self.0.children().filter_map(Entry::cast)
+    }
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct Entry(SyntaxNode);
+
+impl Entry {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            SyntaxKind::Entry => Some(Entry(node)),
+            _ => None,
+        }
+    }
+    pub fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    /// The key of a bare entry or a definition `key = value`.
+    pub fn key(&self) -> Option<Key> {
+        self.0.child(SyntaxKind::Key).map(Key)
+    }
+    pub fn edge(&self) -> Option<Edge> {
+        self.0.child(SyntaxKind::Edge).map(Edge)
+    }
+    /// The id of a named edge `id: A -> B`.
+    pub fn edge_id(&self) -> Option<Key> {
+        self.0
+            .child(SyntaxKind::EdgeId)
+            .and_then(|node| node.child(SyntaxKind::Key))
+            .map(Key)
+    }
+    /// The `=` or `:` token.
+    pub fn operator(&self) -> Option<SyntaxToken> {
+        self.0
+            .tokens()
+            .find(|token| matches!(token.kind(), SyntaxKind::Equal | SyntaxKind::Colon))
+    }
+    pub fn value(&self) -> Option<Value> {
+        self.0.children().find_map(Value::cast)
+    }
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct Edge(SyntaxNode);
+
+impl Edge {
+    pub fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    /// The first end of the edge, unless it is a group.
+    pub fn from(&self) -> Option<Key> {
+        self.endpoints().next().and_then(Key::cast)
+    }
+    /// The second end of the edge, unless it is a group.
+    pub fn to(&self) -> Option<Key> {
+        self.endpoints().nth(1).and_then(Key::cast)
+    }
+    /// The `Key`, `Group` and `Join` nodes of the edge, more than two for a
+    /// chain `A -> B -> C`.
+    pub fn endpoints(&self) -> impl Iterator<Item = SyntaxNode> + '_ {
+        self.0.children().filter(|node| {
+            matches!(
+                node.kind(),
+                SyntaxKind::Key | SyntaxKind::Group | SyntaxKind::Join
+            )
+        })
+    }
+    /// All keys of the edge, including those in groups and joins.
+    pub fn keys(&self) -> impl Iterator<Item = Key> + '_ {
+        self.endpoints().flat_map(|node| match node.kind() {
+            SyntaxKind::Key => vec![Key(node)],
+            _ => node.children().filter_map(Key::cast).collect(),
+        })
+    }
+    /// Whether a side of the edge joins several keys, `X + Y -> Z`.
+    pub fn is_hyperedge(&self) -> bool {
+        self.0.child(SyntaxKind::Join).is_some()
+    }
+    pub fn is_chain(&self) -> bool {
+        self.endpoints().count() > 2
+    }
+    /// Whether an end of the edge is a group `(A, B)`.
+    pub fn is_grouped(&self) -> bool {
+        self.0.child(SyntaxKind::Group).is_some()
+    }
+    /// The operator of the edge, or of its first hop for a chain.
+    pub fn kind(&self) -> Option<EdgeKind> {
+        self.0
+            .tokens()
+            .find(|token| token.kind() == SyntaxKind::Arrow)
+            .and_then(|token| EdgeKind::from_operator(token.text()))
+    }
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct Key(SyntaxNode);
+
+impl Key {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            SyntaxKind::Key => Some(Key(node)),
+            _ => None,
+        }
+    }
+    pub fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub fn token(&self) -> SyntaxToken {
+        self.0.tokens().next().unwrap()
+    }
+    /// The key as written, e.g. `"文字列"` with its quotes.
+    pub fn text(&self) -> String {
+        self.0.text()
+    }
+}
+
+/// A value: a literal, a list, a struct or a message.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Value(SyntaxNode);
+
+impl Value {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            SyntaxKind::Literal
+            | SyntaxKind::List
+            | SyntaxKind::Struct
+            | SyntaxKind::Message
+            | SyntaxKind::Error => Some(Value(node)),
+            _ => None,
+        }
+    }
+    pub fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+    pub fn kind(&self) -> SyntaxKind {
+        self.0.kind()
+    }
+    /// The values of a list or the arguments of a message.
+    pub fn items(&self) -> Vec<Value> {
+        let list = match self.kind() {
+            SyntaxKind::List => Some(self.0.clone()),
+            SyntaxKind::Message => self.0.child(SyntaxKind::Args),
+            _ => None,
+        };
+        list.map_or_else(Vec::new, |list| {
+            list.children().filter_map(Value::cast).collect()
+        })
+    }
+    /// The `key: value` pairs of a struct.
+    pub fn pairs(&self) -> Vec<(Key, Option<Value>)> {
+        self.0
+            .children()
+            .filter(|child| child.kind() == SyntaxKind::Pair)
+            .filter_map(|pair| {
+                let key = pair.children().find_map(Key::cast)?;
+                Some((key, pair.children().find_map(Value::cast)))
+            })
+            .collect()
+    }
+    /// The name of a message, e.g. `Attack` for `Attack()`.
+    pub fn message_name(&self) -> Option<String> {
+        match self.kind() {
+            SyntaxKind::Message => self
+                .0
+                .token(SyntaxKind::Identifier)
+                .map(|token| identifier_text(&token)),
+            _ => None,
+        }
+    }
+}
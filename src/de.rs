@@ -0,0 +1,650 @@
+//! Deserialize Rust data structures from GMML documents with serde.
+//!
+//! A document deserializes as a map from block names to blocks, so blocks
+//! map onto struct fields. A block deserializes as a map of its definitions
+//! (`key = value`, and `from -> to : stmt` keyed by `(from, to)`; bare
+//! entries map to `none`) or as a sequence of its entries; a block holding
+//! a single bare value or the single definition `value = ...` deserializes
+//! as that value, and an empty block as `None`. Lists are sequences,
+//! structs are maps, edges are `(from, to)` tuples in the direction of the
+//! edge, so `A <- B` is `(B, A)`, and symbols and messages select enum
+//! variants, e.g. `Attack(1, 2)` for `Attack(i64, i64)`.
+
+use crate::document::{Block, Document};
+use crate::error::{Error, Location};
+use crate::ser::VALUE;
+use crate::span::Span;
+use crate::value::{GValue, Key as _};
+use serde::de::value::BorrowedStrDeserializer;
+use serde::de::{self, DeserializeOwned, DeserializeSeed, Visitor};
+use serde::forward_to_deserialize_any;
+use std::fmt;
+
+static NULL: GValue = GValue::Null;
+
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error::Deserialize {
+            message: msg.to_string(),
+            location: None,
+        }
+    }
+}
+
+/// Attaches `span` to an error raised while deserializing the entry at
+/// `span`, unless a more precise location is already known.
+fn locate(err: Error, span: Option<Span>) -> Error {
+    match (err, span) {
+        (
+            Error::Deserialize {
+                message,
+                location: None,
+            },
+            Some(span),
+        ) => Error::Deserialize {
+            message,
+            location: Some(Location { file: None, span }),
+        },
+        (err, _) => err,
+    }
+}
+
+/// Parses `src` and deserializes an instance of `T` from it.
+pub fn from_str<T: DeserializeOwned>(src: &str) -> Result<T, Error> {
+    from_document(&crate::parse_str(src)?)
+}
+
+pub fn from_document<'de, T: de::Deserialize<'de>>(document: &'de Document) -> Result<T, Error> {
+    T::deserialize(Deserializer::new(document))
+}
+
+pub fn from_value<'de, T: de::Deserialize<'de>>(value: &'de GValue) -> Result<T, Error> {
+    T::deserialize(ValueDeserializer::new(value))
+}
+
+/// Deserializes a whole document, one field per block.
+pub struct Deserializer<'de> {
+    document: &'de Document,
+}
+
+impl<'de> Deserializer<'de> {
+    pub fn new(document: &'de Document) -> Self {
+        Self { document }
+    }
+}
+
+impl<'de> de::Deserializer<'de> for Deserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_map(MapAccess {
+            iter: self.document.blocks().map(|block| {
+                (
+                    Key::Str(&block.name),
+                    Content::Block(block),
+                    Some(block.name_span),
+                )
+            }),
+            value: None,
+        })
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+/// Deserializes the entries of a single block.
+pub struct BlockDeserializer<'de> {
+    block: &'de Block,
+}
+
+impl<'de> BlockDeserializer<'de> {
+    pub fn new(block: &'de Block) -> Self {
+        Self { block }
+    }
+    fn visit_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_map(MapAccess {
+            iter: self.block.entries.iter().map(|entry| match &entry.node {
+                GValue::Pair(key, value) => {
+                    (Key::Value(key), Content::Value(value), Some(entry.span))
+                }
+                key => (Key::Value(key), Content::Value(&NULL), Some(entry.span)),
+            }),
+            value: None,
+        })
+    }
+    /// The value of a block holding a single bare value, which is how a
+    /// scalar field such as `3` is written, or the definition `value = ...`
+    /// written for content that can not be block entries.
+    fn single(&self) -> Option<(&'de GValue, Span)> {
+        match &self.block.entries[..] {
+            [entry] => match &entry.node {
+                GValue::Pair(key, value) if VALUE.matches_key(key) => Some((value, entry.span)),
+                GValue::Pair(..) => None,
+                value => Some((value, entry.span)),
+            },
+            _ => None,
+        }
+    }
+    fn visit_entry<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.single() {
+            Some((value, span)) => {
+                de::Deserializer::deserialize_any(ValueDeserializer::new(value), visitor)
+                    .map_err(|err| locate(err, Some(span)))
+            }
+            None => de::Deserializer::deserialize_any(self, visitor),
+        }
+    }
+}
+
+macro_rules! forward_to_entry {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                self.visit_entry(visitor)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for BlockDeserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let definitions = self
+            .block
+            .values()
+            .all(|entry| matches!(entry, GValue::Pair(..)));
+        if definitions && !self.block.is_empty() {
+            self.visit_map(visitor)
+        } else {
+            self.deserialize_seq(visitor)
+        }
+    }
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        if let Some((value @ GValue::Vec(_), span)) = self.single() {
+            return de::Deserializer::deserialize_any(ValueDeserializer::new(value), visitor)
+                .map_err(|err| locate(err, Some(span)));
+        }
+        visitor.visit_seq(SeqAccess {
+            iter: self
+                .block
+                .entries
+                .iter()
+                .map(|entry| (&entry.node, Some(entry.span))),
+        })
+    }
+    fn deserialize_tuple<V: Visitor<'de>>(self, _: usize, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        _: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.visit_map(visitor)
+    }
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        _: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.visit_map(visitor)
+    }
+    /// An empty block is `None`, as written for a `None` field.
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        if self.block.is_empty() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let single = self.single().or_else(|| match &self.block.entries[..] {
+            [entry] => Some((&entry.node, entry.span)),
+            _ => None,
+        });
+        match single {
+            Some((value, span)) => ValueDeserializer::new(value)
+                .deserialize_enum(name, variants, visitor)
+                .map_err(|err| locate(err, Some(span))),
+            None => self.deserialize_any(visitor),
+        }
+    }
+
+    forward_to_entry! {
+        deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_i128 deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64
+        deserialize_u128 deserialize_f32 deserialize_f64 deserialize_char deserialize_str
+        deserialize_string
+    }
+
+    forward_to_deserialize_any! {
+        bytes byte_buf unit unit_struct identifier ignored_any
+    }
+}
+
+/// Deserializes a single value.
+pub struct ValueDeserializer<'de> {
+    value: &'de GValue,
+}
+
+impl<'de> ValueDeserializer<'de> {
+    pub fn new(value: &'de GValue) -> Self {
+        Self { value }
+    }
+}
+
+fn visit_values<'de, V: Visitor<'de>>(
+    values: &'de [GValue],
+    visitor: V,
+) -> Result<V::Value, Error> {
+    visitor.visit_seq(SeqAccess {
+        iter: values.iter().map(|value| (value, None)),
+    })
+}
+
+fn visit_pair<'de, V: Visitor<'de>>(
+    first: &'de GValue,
+    second: &'de GValue,
+    visitor: V,
+) -> Result<V::Value, Error> {
+    visitor.visit_seq(SeqAccess {
+        iter: vec![(first, None), (second, None)].into_iter(),
+    })
+}
+
+fn unexpected(value: &GValue) -> de::Unexpected<'_> {
+    match value {
+        GValue::String(s) | GValue::Symbol(s) => de::Unexpected::Str(s),
+        GValue::Integer(i) => de::Unexpected::Signed(*i),
+        GValue::Float(f) => de::Unexpected::Float(*f),
+        GValue::Bool(b) => de::Unexpected::Bool(*b),
+        GValue::Null => de::Unexpected::Unit,
+        GValue::Vec(_) | GValue::Edge(..) | GValue::Pair(..) => de::Unexpected::Seq,
+        GValue::Map(_) | GValue::HyperEdge { .. } | GValue::Named { .. } => de::Unexpected::Map,
+        GValue::Message(..) => de::Unexpected::Other("message"),
+        GValue::Path(_) => de::Unexpected::Other("path"),
+    }
+}
+
+impl<'de> de::Deserializer<'de> for ValueDeserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.value {
+            GValue::String(s) | GValue::Symbol(s) => visitor.visit_borrowed_str(s),
+            GValue::Path(names) => visitor.visit_string(names.join(".")),
+            GValue::Integer(i) => visitor.visit_i64(*i),
+            GValue::Float(f) => visitor.visit_f64(*f),
+            GValue::Bool(b) => visitor.visit_bool(*b),
+            GValue::Null => visitor.visit_unit(),
+            GValue::Vec(values) => visit_values(values, visitor),
+            GValue::Edge(from, to, _) | GValue::Pair(from, to) => visit_pair(from, to, visitor),
+            GValue::Map(map) => visitor.visit_map(MapAccess {
+                iter: map
+                    .iter()
+                    .map(|(key, value)| (Key::Value(key), Content::Value(value), None)),
+                value: None,
+            }),
+            GValue::Message(name, args) => visitor.visit_map(MapAccess {
+                iter: std::iter::once((Key::Str(name), Content::Args(args), None)),
+                value: None,
+            }),
+            GValue::Named { id, edge } => visitor.visit_map(MapAccess {
+                iter: std::iter::once((Key::Str(id), Content::Value(edge), None)),
+                value: None,
+            }),
+            GValue::HyperEdge {
+                sources, targets, ..
+            } => visitor.visit_map(MapAccess {
+                iter: vec![
+                    (Key::Str("sources"), Content::Args(sources), None),
+                    (Key::Str("targets"), Content::Args(targets), None),
+                ]
+                .into_iter(),
+                value: None,
+            }),
+        }
+    }
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.value {
+            GValue::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.value {
+            GValue::Message(_, args) => visit_values(args, visitor),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+    fn deserialize_tuple<V: Visitor<'de>>(self, _: usize, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        _: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        _: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match self.value {
+            GValue::String(name) | GValue::Symbol(name) => visitor.visit_enum(EnumAccess {
+                name,
+                content: None,
+            }),
+            GValue::Message(name, args) => visitor.visit_enum(EnumAccess {
+                name,
+                content: Some(Content::Args(args)),
+            }),
+            GValue::Map(map) if map.len() == 1 => {
+                let (key, value) = map.iter().next().unwrap();
+                match key {
+                    GValue::String(name) | GValue::Symbol(name) => visitor.visit_enum(EnumAccess {
+                        name,
+                        content: Some(Content::Value(value)),
+                    }),
+                    _ => Err(de::Error::invalid_type(unexpected(key), &"a variant name")),
+                }
+            }
+            value => Err(de::Error::invalid_type(
+                unexpected(value),
+                &"an enum variant",
+            )),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct map struct identifier ignored_any
+    }
+}
+
+/// A map key: either a block name or a GMML value.
+enum Key<'de> {
+    Str(&'de str),
+    Value(&'de GValue),
+}
+
+/// The value side of a map entry or an enum variant.
+enum Content<'de> {
+    Block(&'de Block),
+    Value(&'de GValue),
+    Args(&'de [GValue]),
+}
+
+impl<'de> Content<'de> {
+    fn visit_entry<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Content::Block(block) => BlockDeserializer::new(block).visit_entry(visitor),
+            _ => de::Deserializer::deserialize_any(self, visitor),
+        }
+    }
+}
+
+impl<'de> de::Deserializer<'de> for Content<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Content::Block(block) => BlockDeserializer::new(block).deserialize_any(visitor),
+            Content::Value(value) => ValueDeserializer::new(value).deserialize_any(visitor),
+            Content::Args(args) => visit_values(args, visitor),
+        }
+    }
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Content::Block(block) => BlockDeserializer::new(block).deserialize_option(visitor),
+            Content::Value(value) => ValueDeserializer::new(value).deserialize_option(visitor),
+            Content::Args(_) => visitor.visit_some(self),
+        }
+    }
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Content::Block(block) => BlockDeserializer::new(block).deserialize_map(visitor),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match self {
+            Content::Block(block) => {
+                BlockDeserializer::new(block).deserialize_struct(name, fields, visitor)
+            }
+            // Struct variants take either a single struct or positional
+            // arguments, e.g. `Move({x: 1, y: 2})` or `Move(1, 2)`.
+            Content::Args([value @ GValue::Map(_)]) => {
+                ValueDeserializer::new(value).deserialize_any(visitor)
+            }
+            _ => self.deserialize_any(visitor),
+        }
+    }
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Content::Block(block) => BlockDeserializer::new(block).deserialize_seq(visitor),
+            Content::Value(value) => ValueDeserializer::new(value).deserialize_seq(visitor),
+            Content::Args(args) => visit_values(args, visitor),
+        }
+    }
+    fn deserialize_tuple<V: Visitor<'de>>(self, _: usize, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        _: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match self {
+            Content::Block(block) => {
+                BlockDeserializer::new(block).deserialize_enum(name, variants, visitor)
+            }
+            Content::Value(value) => {
+                ValueDeserializer::new(value).deserialize_enum(name, variants, visitor)
+            }
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    forward_to_entry! {
+        deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_i128 deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64
+        deserialize_u128 deserialize_f32 deserialize_f64 deserialize_char deserialize_str
+        deserialize_string
+    }
+
+    forward_to_deserialize_any! {
+        bytes byte_buf unit unit_struct identifier ignored_any
+    }
+}
+
+struct SeqAccess<I> {
+    iter: I,
+}
+
+impl<'de, I> de::SeqAccess<'de> for SeqAccess<I>
+where
+    I: Iterator<Item = (&'de GValue, Option<Span>)>,
+{
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Error> {
+        match self.iter.next() {
+            Some((value, span)) => seed
+                .deserialize(ValueDeserializer::new(value))
+                .map(Some)
+                .map_err(|err| locate(err, span)),
+            None => Ok(None),
+        }
+    }
+}
+
+struct MapAccess<'de, I> {
+    iter: I,
+    value: Option<(Content<'de>, Option<Span>)>,
+}
+
+impl<'de, I> de::MapAccess<'de> for MapAccess<'de, I>
+where
+    I: Iterator<Item = (Key<'de>, Content<'de>, Option<Span>)>,
+{
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Error> {
+        match self.iter.next() {
+            Some((key, value, span)) => {
+                self.value = Some((value, span));
+                let key = match key {
+                    Key::Str(name) => seed.deserialize(BorrowedStrDeserializer::new(name)),
+                    Key::Value(key) => seed.deserialize(ValueDeserializer::new(key)),
+                };
+                key.map(Some).map_err(|err| locate(err, span))
+            }
+            None => Ok(None),
+        }
+    }
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        match self.value.take() {
+            Some((value, span)) => seed.deserialize(value).map_err(|err| locate(err, span)),
+            None => Err(de::Error::custom("value is missing")),
+        }
+    }
+}
+
+struct EnumAccess<'de> {
+    name: &'de str,
+    content: Option<Content<'de>>,
+}
+
+impl<'de> de::EnumAccess<'de> for EnumAccess<'de> {
+    type Error = Error;
+    type Variant = VariantAccess<'de>;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self::Variant), Error> {
+        let variant = seed.deserialize(BorrowedStrDeserializer::<Error>::new(self.name))?;
+        Ok((
+            variant,
+            VariantAccess {
+                content: self.content,
+            },
+        ))
+    }
+}
+
+struct VariantAccess<'de> {
+    content: Option<Content<'de>>,
+}
+
+impl<'de> de::VariantAccess<'de> for VariantAccess<'de> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        match self.content {
+            None | Some(Content::Args([])) => Ok(()),
+            Some(_) => Err(de::Error::invalid_type(
+                de::Unexpected::Other("message arguments"),
+                &"a unit variant",
+            )),
+        }
+    }
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Error> {
+        match self.content {
+            Some(Content::Args([value])) | Some(Content::Value(value)) => {
+                seed.deserialize(ValueDeserializer::new(value))
+            }
+            Some(Content::Args(args)) => Err(de::Error::invalid_length(
+                args.len(),
+                &"a message with one argument",
+            )),
+            _ => Err(de::Error::invalid_type(
+                de::Unexpected::UnitVariant,
+                &"a newtype variant",
+            )),
+        }
+    }
+    fn tuple_variant<V: Visitor<'de>>(self, _: usize, visitor: V) -> Result<V::Value, Error> {
+        match self.content {
+            Some(content) => de::Deserializer::deserialize_seq(content, visitor),
+            None => Err(de::Error::invalid_type(
+                de::Unexpected::UnitVariant,
+                &"a tuple variant",
+            )),
+        }
+    }
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match self.content {
+            Some(content) => de::Deserializer::deserialize_struct(content, "", fields, visitor),
+            None => Err(de::Error::invalid_type(
+                de::Unexpected::UnitVariant,
+                &"a struct variant",
+            )),
+        }
+    }
+}
@@ -0,0 +1,361 @@
+use crate::error::Error;
+use crate::span::Span;
+use std::fmt::Write;
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const BLUE: &str = "\x1b[1;34m";
+const CYAN: &str = "\x1b[1;36m";
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Severity {
+    fn as_str(self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }
+    }
+    fn color(self) -> &'static str {
+        match self {
+            Severity::Error => RED,
+            Severity::Warning => YELLOW,
+        }
+    }
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+    pub primary: bool,
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub file: Option<String>,
+    pub labels: Vec<Label>,
+    pub notes: Vec<String>,
+    pub help: Vec<String>,
+}
+
+struct Style {
+    enabled: bool,
+}
+
+impl Style {
+    fn paint(&self, color: &str, text: &str) -> String {
+        if self.enabled && !text.is_empty() {
+            format!("{}{}{}", color, text, RESET)
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+impl Diagnostic {
+    pub fn new(severity: Severity, message: &str) -> Self {
+        Self {
+            severity,
+            message: message.to_string(),
+            file: None,
+            labels: Vec::new(),
+            notes: Vec::new(),
+            help: Vec::new(),
+        }
+    }
+    pub fn error(message: &str) -> Self {
+        Self::new(Severity::Error, message)
+    }
+    pub fn warning(message: &str) -> Self {
+        Self::new(Severity::Warning, message)
+    }
+    pub fn with_file(mut self, file: &str) -> Self {
+        self.file = Some(file.to_string());
+        self
+    }
+    pub fn with_label(mut self, span: Span, message: &str) -> Self {
+        self.labels.push(Label {
+            span,
+            message: message.to_string(),
+            primary: true,
+        });
+        self
+    }
+    pub fn with_secondary_label(mut self, span: Span, message: &str) -> Self {
+        self.labels.push(Label {
+            span,
+            message: message.to_string(),
+            primary: false,
+        });
+        self
+    }
+    pub fn with_note(mut self, note: &str) -> Self {
+        self.notes.push(note.to_string());
+        self
+    }
+    pub fn with_help(mut self, help: &str) -> Self {
+        self.help.push(help.to_string());
+        self
+    }
+
+    /// Renders the diagnostic against `source` in the style of rustc,
+    /// optionally with ANSI colors.
+    pub fn render(&self, source: &str, color: bool) -> String {
+        let style = Style { enabled: color };
+        let mut out = String::new();
+        let severity = self.severity.as_str();
+        let _ = writeln!(
+            out,
+            "{}{}",
+            style.paint(self.severity.color(), severity),
+            style.paint(BOLD, &format!(": {}", self.message))
+        );
+        let lines = LineIndex::new(source);
+        let mut rows: Vec<(usize, usize, usize, &Label, bool)> = Vec::new();
+        for label in &self.labels {
+            let start = label.span.start.min(source.len());
+            let end = label.span.end.min(source.len()).max(start);
+            let first = lines.line_of(start);
+            let last = lines.line_of(end.saturating_sub(1).max(start));
+            for line in first..=last {
+                let (line_start, line_end) = lines.range(line);
+                let from = start.max(line_start);
+                let to = if line == last {
+                    end.min(line_end)
+                } else {
+                    line_end
+                };
+                let from = from.min(to);
+                rows.push((
+                    line,
+                    from - line_start,
+                    to - line_start,
+                    label,
+                    line == last,
+                ));
+            }
+        }
+        rows.sort_by_key(|&(line, from, _, label, _)| (line, from, !label.primary));
+        let width = rows
+            .iter()
+            .map(|&(line, ..)| (line + 1).to_string().len())
+            .max()
+            .unwrap_or(1);
+        let gutter = " ".repeat(width);
+        let bar = style.paint(BLUE, "|");
+        if let Some(primary) = self.labels.iter().find(|label| label.primary) {
+            let line = lines.line_of(primary.span.start.min(source.len()));
+            let (line_start, _) = lines.range(line);
+            let column = source[line_start..primary.span.start.min(source.len())]
+                .chars()
+                .count()
+                + 1;
+            let file = self.file.as_deref().unwrap_or("<input>");
+            let _ = writeln!(
+                out,
+                "{}{} {}:{}:{}",
+                gutter,
+                style.paint(BLUE, "-->"),
+                file,
+                line + 1,
+                column
+            );
+        } else if let Some(file) = &self.file {
+            let _ = writeln!(out, "{}{} {}", gutter, style.paint(BLUE, "-->"), file);
+        }
+        if !rows.is_empty() {
+            let _ = writeln!(out, "{} {}", gutter, bar);
+        }
+        let mut last_line = None;
+        for &(line, from, to, label, is_last) in &rows {
+            let (line_start, line_end) = lines.range(line);
+            let text = &source[line_start..line_end];
+            if last_line != Some(line) {
+                if last_line.is_some_and(|last| line > last + 1) {
+                    let _ = writeln!(out, "{}", style.paint(BLUE, "..."));
+                }
+                let number = format!("{:>width$}", line + 1, width = width);
+                let _ = writeln!(
+                    out,
+                    "{} {} {}",
+                    style.paint(BLUE, &number),
+                    bar,
+                    text.replace('\t', " ")
+                );
+                last_line = Some(line);
+            }
+            let padding = text[..from].chars().count();
+            let length = text[from..to].chars().count().max(1);
+            let (marker, color) = if label.primary {
+                ("^", self.severity.color())
+            } else {
+                ("-", BLUE)
+            };
+            let underline = marker.repeat(length);
+            let message = if is_last { label.message.as_str() } else { "" };
+            let _ = writeln!(
+                out,
+                "{} {} {}{}",
+                gutter,
+                bar,
+                " ".repeat(padding),
+                style
+                    .paint(color, &format!("{} {}", underline, message))
+                    .trim_end()
+            );
+        }
+        if !self.notes.is_empty() || !self.help.is_empty() {
+            let _ = writeln!(out, "{} {}", gutter, bar);
+        }
+        for note in &self.notes {
+            let _ = writeln!(
+                out,
+                "{} {} {}: {}",
+                gutter,
+                style.paint(BLUE, "="),
+                style.paint(BOLD, "note"),
+                note
+            );
+        }
+        for help in &self.help {
+            let _ = writeln!(
+                out,
+                "{} {} {}: {}",
+                gutter,
+                style.paint(BLUE, "="),
+                style.paint(CYAN, "help"),
+                help
+            );
+        }
+        out
+    }
+}
+
+struct LineIndex {
+    starts: Vec<usize>,
+    ends: Vec<usize>,
+}
+
+impl LineIndex {
+    fn new(source: &str) -> Self {
+        let mut starts = vec![0];
+        let mut ends = Vec::new();
+        for (i, c) in source.char_indices() {
+            if c == '\n' {
+                let end = if source[..i].ends_with('\r') {
+                    i - 1
+                } else {
+                    i
+                };
+                ends.push(end);
+                starts.push(i + 1);
+            }
+        }
+        ends.push(source.len());
+        Self { starts, ends }
+    }
+    fn line_of(&self, offset: usize) -> usize {
+        match self.starts.binary_search(&offset) {
+            Ok(line) => line,
+            Err(line) => line - 1,
+        }
+    }
+    fn range(&self, line: usize) -> (usize, usize) {
+        (self.starts[line], self.ends[line])
+    }
+}
+
+impl From<&Error> for Diagnostic {
+    fn from(err: &Error) -> Self {
+        let mut diagnostic = Diagnostic::error(&err.message());
+        if let Some(location) = err.location() {
+            if let Some(file) = &location.file {
+                diagnostic = diagnostic.with_file(file);
+            }
+        }
+        match err {
+            Error::Io(_)
+            | Error::Type { .. }
+            | Error::MissingKey { .. }
+            | Error::MissingBlock { .. }
+            | Error::IndexOutOfRange { .. }
+            | Error::InvalidQuery { .. }
+            | Error::Serialize { .. } => diagnostic,
+            Error::Deserialize { location, .. } => match location {
+                Some(location) => {
+                    diagnostic.with_label(location.span, "while deserializing this entry")
+                }
+                None => diagnostic,
+            },
+            Error::UnexpectedChar { location, .. } => {
+                diagnostic.with_label(location.span, "unexpected character")
+            }
+            Error::UnterminatedString { location } => diagnostic
+                .with_label(location.span, "string starts here")
+                .with_help("add a closing `\"`"),
+            Error::UnterminatedIdentifier { location } => diagnostic
+                .with_label(location.span, "identifier starts here")
+                .with_help("add a closing `` ` ``"),
+            Error::InvalidNumber { location, .. } => {
+                diagnostic.with_label(location.span, "invalid number")
+            }
+            Error::NumberOverflow { location, .. } => diagnostic
+                .with_label(location.span, "out of range")
+                .with_note("integers must fit in 64 bits and floats must be finite"),
+            Error::InvalidEscape { location, .. } => diagnostic
+                .with_label(location.span, "invalid escape")
+                .with_help(
+                    "valid escapes are `\\\"`, `\\\\`, `\\n`, `\\r`, `\\t`, `\\0` and `\\u{...}`; \
+                     use a raw string `r\"...\"` to avoid escaping",
+                ),
+            Error::UnexpectedToken {
+                expected, location, ..
+            } => diagnostic.with_label(location.span, &format!("expected {}", expected)),
+            Error::UnexpectedEof { expected, location } => {
+                diagnostic.with_label(location.span, &format!("expected {}", expected))
+            }
+            Error::DuplicateKey {
+                first, location, ..
+            } => diagnostic
+                .with_label(location.span, "duplicate key")
+                .with_secondary_label(*first, "first defined here"),
+            Error::DuplicateBlock {
+                first, location, ..
+            } => diagnostic
+                .with_label(location.span, "duplicate block")
+                .with_secondary_label(*first, "first defined here")
+                .with_help("merge the blocks or choose another `DuplicateBlocks` policy"),
+            Error::DuplicateEdgeId {
+                first, location, ..
+            } => diagnostic
+                .with_label(location.span, "duplicate edge id")
+                .with_secondary_label(*first, "first used here"),
+            Error::ReservedKeyword { keyword, location } => diagnostic
+                .with_label(location.span, "reserved keyword")
+                .with_help(&format!(
+                    "quote it with backticks to use it as a name: `` `{}` ``",
+                    keyword
+                )),
+            Error::MissingParentheses {
+                message,
+                message_span,
+                location,
+            } => diagnostic
+                .with_label(location.span, "expected `(`")
+                .with_secondary_label(*message_span, "message called here")
+                .with_help(&format!(
+                    "parentheses can not be omitted: write `{}(...)`",
+                    message
+                )),
+        }
+    }
+}
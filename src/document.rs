@@ -0,0 +1,305 @@
+use crate::error::Error;
+use crate::span::{Span, Spanned};
+use crate::value::{self, EdgeRef, GValue, HyperEdgeRef, Key};
+use std::collections::HashMap;
+use std::fmt;
+use std::iter;
+use std::ops::Index;
+use std::slice;
+use std::sync::OnceLock;
+
+/// What to do when a block name appears more than once in a document.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub enum DuplicateBlocks {
+    /// Reject the document with `Error::DuplicateBlock`.
+    #[default]
+    Error,
+    /// Append the entries of later blocks to the first block of that name.
+    Merge,
+    /// Keep every block; `Document::get` returns the first one.
+    KeepAll,
+}
+
+/// A block `[name]` and its entries in source order.
+///
+/// An edge named `id: A -> B` is looked up with `Block::edge`, and
+/// `Block::edges_between` lists the parallel edges between two nodes.
+#[derive(Debug, Clone)]
+pub struct Block {
+    pub name: String,
+    pub name_span: Span,
+    pub span: Span,
+    pub entries: Vec<Spanned<GValue>>,
+}
+
+impl Block {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            name_span: Span::default(),
+            span: Span::default(),
+            entries: Vec::new(),
+        }
+    }
+    /// The names along the dotted path of a nested block, `["Model",
+    /// "Attack"]` for `[Model.Attack]`.
+    pub fn path(&self) -> impl Iterator<Item = &str> {
+        self.name.split('.')
+    }
+    /// The name of the enclosing block, `Model` for `[Model.Attack]`.
+    pub fn parent(&self) -> Option<&str> {
+        self.name.rsplit_once('.').map(|(parent, _)| parent)
+    }
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+    pub fn values(&self) -> impl Iterator<Item = &GValue> {
+        self.entries.iter().map(|entry| &entry.node)
+    }
+    pub fn push(&mut self, value: GValue) {
+        self.entries.push(Spanned::new(value, Span::default()));
+    }
+    /// Looks up the value of the leaf definition `key = ...`.
+    pub fn get<K: Key + ?Sized + fmt::Display>(&self, key: &K) -> Result<&GValue, Error> {
+        self.leaf_defs()
+            .find(|(k, _)| key.matches_key(k))
+            .map(|(_, v)| v)
+            .ok_or_else(|| Error::MissingKey {
+                key: key.to_string(),
+            })
+    }
+    pub fn edges(&self) -> impl Iterator<Item = EdgeRef<'_>> {
+        self.values().filter_map(EdgeRef::from_entry)
+    }
+    pub fn hyperedges(&self) -> impl Iterator<Item = HyperEdgeRef<'_>> {
+        self.values().filter_map(HyperEdgeRef::from_entry)
+    }
+    /// Looks up the edge named `id`, written `id: from -> to`.
+    pub fn edge(&self, id: &str) -> Option<EdgeRef<'_>> {
+        self.edges().find(|edge| edge.id == Some(id))
+    }
+    /// Looks up the hyperedge named `id`.
+    pub fn hyperedge(&self, id: &str) -> Option<HyperEdgeRef<'_>> {
+        self.hyperedges().find(|edge| edge.id == Some(id))
+    }
+    /// The parallel edges from `from` to `to`, in source order.
+    pub fn edges_between<'a, K: Key + ?Sized>(
+        &'a self,
+        from: &'a K,
+        to: &'a K,
+    ) -> impl Iterator<Item = EdgeRef<'a>> {
+        self.edges()
+            .filter(move |edge| from.matches_key(edge.from) && to.matches_key(edge.to))
+    }
+    pub fn leaf_defs(&self) -> impl Iterator<Item = (&GValue, &GValue)> {
+        self.values().filter_map(value::leaf_def)
+    }
+    pub fn to_value(&self) -> GValue {
+        GValue::Vec(self.values().cloned().collect())
+    }
+}
+
+/// Spans are ignored when comparing blocks.
+impl PartialEq for Block {
+    fn eq(&self, other: &Block) -> bool {
+        self.name == other.name && self.values().eq(other.values())
+    }
+}
+
+/// The nesting of the blocks of a document, built from their names.
+#[derive(Debug, Clone, Default)]
+struct Tree {
+    /// The first block of each name.
+    index: HashMap<String, usize>,
+    /// The nearest enclosing block of each block.
+    parents: Vec<Option<usize>>,
+    children: Vec<Vec<usize>>,
+    roots: Vec<usize>,
+}
+
+impl Tree {
+    fn new(blocks: &[Block]) -> Self {
+        let mut tree = Tree {
+            index: HashMap::new(),
+            parents: Vec::with_capacity(blocks.len()),
+            children: vec![Vec::new(); blocks.len()],
+            roots: Vec::new(),
+        };
+        for (i, block) in blocks.iter().enumerate() {
+            tree.index.entry(block.name.clone()).or_insert(i);
+        }
+        for (i, block) in blocks.iter().enumerate() {
+            let parent = tree.enclosing(&block.name);
+            match parent {
+                Some(parent) => tree.children[parent].push(i),
+                None => tree.roots.push(i),
+            }
+            tree.parents.push(parent);
+        }
+        tree
+    }
+    /// The nearest block enclosing `name`. Missing levels are skipped, so
+    /// `[A.B.C]` is nested in `[A]` if there is no `[A.B]`.
+    fn enclosing(&self, name: &str) -> Option<usize> {
+        let mut path = name;
+        while let Some((parent, _)) = path.rsplit_once('.') {
+            if let Some(&i) = self.index.get(parent) {
+                return Some(i);
+            }
+            path = parent;
+        }
+        None
+    }
+}
+
+/// A parsed GMML document, keeping its blocks in source order.
+///
+/// A block named with a dotted path such as `[Model.Attack]` is nested in
+/// the nearest enclosing block, here `[Model]`. `roots`, `children` and
+/// `ancestors` walk this tree, and `get_inherited` and `inherited_defs` fall
+/// back to the enclosing blocks for definitions a block does not have. The
+/// tree is indexed on first use and rebuilt after the blocks change.
+#[derive(Clone, Default)]
+pub struct Document {
+    blocks: Vec<Block>,
+    tree: OnceLock<Tree>,
+}
+
+impl Document {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+    pub fn blocks(&self) -> slice::Iter<'_, Block> {
+        self.blocks.iter()
+    }
+    pub fn blocks_mut(&mut self) -> slice::IterMut<'_, Block> {
+        self.tree.take();
+        self.blocks.iter_mut()
+    }
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.blocks.iter().map(|block| block.name.as_str())
+    }
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+    pub fn get(&self, name: &str) -> Option<&Block> {
+        self.blocks.iter().find(|block| block.name == name)
+    }
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Block> {
+        self.tree.take();
+        self.blocks.iter_mut().find(|block| block.name == name)
+    }
+    pub fn get_all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Block> {
+        self.blocks.iter().filter(move |block| block.name == name)
+    }
+    pub fn push(&mut self, block: Block) {
+        self.tree.take();
+        self.blocks.push(block);
+    }
+
+    fn tree(&self) -> &Tree {
+        self.tree.get_or_init(|| Tree::new(&self.blocks))
+    }
+    /// The blocks that are not nested in another one, such as `[Model]`.
+    pub fn roots(&self) -> impl Iterator<Item = &Block> {
+        self.tree().roots.iter().map(move |&i| &self.blocks[i])
+    }
+    /// The blocks nested directly in the block `name`, such as
+    /// `[Model.Attack]` in `[Model]`.
+    pub fn children(&self, name: &str) -> impl Iterator<Item = &Block> {
+        let tree = self.tree();
+        let children = match tree.index.get(name) {
+            Some(&i) => &tree.children[i][..],
+            None => &[],
+        };
+        children.iter().map(move |&i| &self.blocks[i])
+    }
+    /// The blocks enclosing `name`, innermost first.
+    pub fn ancestors(&self, name: &str) -> impl Iterator<Item = &Block> {
+        let tree = self.tree();
+        iter::successors(tree.enclosing(name), move |&i| tree.parents[i])
+            .map(move |i| &self.blocks[i])
+    }
+    /// The block `name` followed by the blocks enclosing it.
+    fn scopes<'a>(&'a self, name: &str) -> impl Iterator<Item = &'a Block> {
+        let own = self.tree().index.get(name).map(|&i| &self.blocks[i]);
+        own.into_iter().chain(self.ancestors(name))
+    }
+    /// Looks up the leaf definition `key = ...` of the block `name`, falling
+    /// back to the nearest enclosing block that defines it.
+    pub fn get_inherited<K: Key + ?Sized + fmt::Display>(
+        &self,
+        name: &str,
+        key: &K,
+    ) -> Result<&GValue, Error> {
+        self.scopes(name)
+            .find_map(|block| block.get(key).ok())
+            .ok_or_else(|| Error::MissingKey {
+                key: key.to_string(),
+            })
+    }
+    /// The leaf definitions in effect in the block `name`: its own ones,
+    /// followed by those of enclosing blocks that it does not override.
+    pub fn inherited_defs(&self, name: &str) -> Vec<(&GValue, &GValue)> {
+        let mut defs: Vec<(&GValue, &GValue)> = Vec::new();
+        for block in self.scopes(name) {
+            for (key, value) in block.leaf_defs() {
+                if !defs.iter().any(|(defined, _)| defined.matches_key(key)) {
+                    defs.push((key, value));
+                }
+            }
+        }
+        defs
+    }
+}
+
+/// The tree index is ignored when comparing documents.
+impl PartialEq for Document {
+    fn eq(&self, other: &Document) -> bool {
+        self.blocks == other.blocks
+    }
+}
+
+impl fmt::Debug for Document {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Document")
+            .field("blocks", &self.blocks)
+            .finish()
+    }
+}
+
+impl Index<&str> for Document {
+    type Output = Block;
+
+    fn index(&self, name: &str) -> &Block {
+        self.get(name)
+            .unwrap_or_else(|| panic!("no block named `{}`", name))
+    }
+}
+
+impl IntoIterator for Document {
+    type Item = Block;
+    type IntoIter = std::vec::IntoIter<Block>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.blocks.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Document {
+    type Item = &'a Block;
+    type IntoIter = slice::Iter<'a, Block>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.blocks.iter()
+    }
+}
@@ -0,0 +1,324 @@
+//! Format-preserving editing of GMML source.
+//!
+//! `DocumentMut` keeps the original text and applies each change as a
+//! small splice located through the lossless tree in `cst`, so comments,
+//! blank lines and the layout of untouched entries are left as they are.
+//! Every edit is checked by re-parsing the result; an edit that would make
+//! the document invalid is rejected and leaves it unchanged.
+
+use crate::cst::{self, SyntaxKind};
+use crate::document::Document;
+use crate::error::Error;
+use crate::parse::{Scanner, Token};
+use crate::ser::{block_name, check_entry, check_key, check_value};
+use crate::value::{EdgeKind, GValue, Key};
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+
+#[derive(Debug, Clone)]
+pub struct DocumentMut {
+    src: String,
+    tree: cst::Document,
+}
+
+/// Reads the value of a key as written in the source.
+fn key_value(key: &cst::Key) -> Option<GValue> {
+    let mut toks = Scanner::from_str(&key.text());
+    let tok = toks.next()?.ok()?.node;
+    Some(match tok {
+        Token::Identifier(name) => {
+            let mut names = vec![name];
+            // the rest of a dotted path `Router.eth0` alternates `.` and names
+            while let Some(Ok(tok)) = toks.nth(1) {
+                match tok.node {
+                    Token::Identifier(name) => names.push(name),
+                    _ => return None,
+                }
+            }
+            match names.len() {
+                1 => GValue::Symbol(names.remove(0)),
+                _ => GValue::Path(names),
+            }
+        }
+        Token::String(string) => GValue::String(string),
+        Token::Integer(number) => GValue::Integer(number),
+        Token::Float(number) => GValue::Float(number),
+        _ => return None,
+    })
+}
+
+fn key_matches<K: Key + ?Sized>(key: &K, target: Option<cst::Key>) -> bool {
+    target
+        .and_then(|target| key_value(&target))
+        .is_some_and(|target| key.matches_key(&target))
+}
+
+fn missing_key<K: fmt::Display + ?Sized>(key: &K) -> Error {
+    Error::MissingKey {
+        key: key.to_string(),
+    }
+}
+
+impl DocumentMut {
+    pub fn parse(src: &str) -> Result<Self, Error> {
+        crate::parse_str(src)?;
+        Ok(Self {
+            src: src.to_string(),
+            tree: cst::parse(src),
+        })
+    }
+    pub fn as_str(&self) -> &str {
+        &self.src
+    }
+    pub fn cst(&self) -> &cst::Document {
+        &self.tree
+    }
+    /// Parses the current text into a `Document`.
+    pub fn to_document(&self) -> Result<Document, Error> {
+        crate::parse_str(&self.src)
+    }
+
+    /// Replaces `range` with `text`, keeping the change only if the result
+    /// still parses.
+    fn splice(&mut self, range: Range<usize>, text: &str) -> Result<(), Error> {
+        let mut src = self.src.clone();
+        src.replace_range(range, text);
+        crate::parse_str(&src)?;
+        self.tree = cst::parse(&src);
+        self.src = src;
+        Ok(())
+    }
+    /// The line ending used by the document, that of its first line.
+    fn newline(&self) -> &'static str {
+        match self.src.find('\n') {
+            Some(i) if self.src[..i].ends_with('\r') => "\r\n",
+            _ => "\n",
+        }
+    }
+    fn line_start(&self, pos: usize) -> usize {
+        self.src[..pos].rfind('\n').map_or(0, |i| i + 1)
+    }
+    /// The position after the newline ending the line at `pos`.
+    fn line_end(&self, pos: usize) -> usize {
+        self.src[pos..]
+            .find('\n')
+            .map_or(self.src.len(), |i| pos + i + 1)
+    }
+    fn block(&self, name: &str) -> Result<cst::Block, Error> {
+        self.tree.block(name).ok_or_else(|| Error::MissingBlock {
+            name: name.to_string(),
+        })
+    }
+    fn find_entry<F>(&self, block: &str, predicate: F) -> Result<Option<cst::Entry>, Error>
+    where
+        F: Fn(&cst::Entry) -> bool,
+    {
+        Ok(self.block(block)?.entries().find(|entry| predicate(entry)))
+    }
+
+    /// Appends an empty block `[name]` at the end of the document.
+    pub fn push_block(&mut self, name: &str) -> Result<(), Error> {
+        let name = block_name(name)?;
+        let newline = self.newline();
+        let mut text = String::new();
+        if !self.src.is_empty() && !self.src.ends_with('\n') {
+            text.push_str(newline);
+        }
+        if !self.src.trim().is_empty() {
+            text.push_str(newline);
+        }
+        text.push_str(&format!("[{}]{}", name, newline));
+        let end = self.src.len();
+        self.splice(end..end, &text)
+    }
+    pub fn rename_block(&mut self, name: &str, new_name: &str) -> Result<(), Error> {
+        let text = block_name(new_name)?.to_string();
+        let tokens = self.block(name)?.name_tokens();
+        let range = tokens[0].text_range().start..tokens[tokens.len() - 1].text_range().end;
+        self.splice(range, &text)
+    }
+    /// Removes the block with its entries, up to the next block header.
+    pub fn remove_block(&mut self, name: &str) -> Result<(), Error> {
+        let range = self.block(name)?.syntax().text_range();
+        self.splice(range, "")
+    }
+
+    /// Appends `entry` to the block, after its last entry and with the same
+    /// indentation. `entry` is written in GMML syntax, e.g. a
+    /// `GValue::Pair` as `key = value`.
+    pub fn push(&mut self, block: &str, entry: GValue) -> Result<(), Error> {
+        check_entry(&entry)?;
+        let block = self.block(block)?;
+        let (pos, indent) = match block.entries().last() {
+            Some(last) => {
+                let range = last.syntax().text_range();
+                let line_start = self.line_start(range.start);
+                let indent = &self.src[line_start..range.start];
+                let indent = if indent.trim().is_empty() {
+                    indent.to_string()
+                } else {
+                    String::new()
+                };
+                (self.line_end(range.end), indent)
+            }
+            None => {
+                let header = block.header().unwrap().text_range();
+                (self.line_end(header.end), String::new())
+            }
+        };
+        let newline = self.newline();
+        let mut text = String::new();
+        if pos == self.src.len() && !self.src.ends_with('\n') {
+            text.push_str(newline);
+        }
+        text.push_str(&format!("{}{}{}", indent, entry, newline));
+        self.splice(pos..pos, &text)
+    }
+    /// Appends the edge `from -> to`, with the statement `: stmt` if any.
+    pub fn push_edge(
+        &mut self,
+        block: &str,
+        from: GValue,
+        to: GValue,
+        stmt: Option<GValue>,
+    ) -> Result<(), Error> {
+        let edge = GValue::Edge(Box::new(from), Box::new(to), EdgeKind::Directed);
+        match stmt {
+            Some(stmt) => self.push(block, GValue::Pair(Box::new(edge), Box::new(stmt))),
+            None => self.push(block, edge),
+        }
+    }
+
+    /// Sets the value of the definition `key = ...`, replacing only the
+    /// value text, or appends the definition if the block has none.
+    pub fn set(&mut self, block: &str, key: GValue, value: GValue) -> Result<(), Error> {
+        check_key(&key)?;
+        check_value(&value)?;
+        let entry = self.find_entry(block, |entry| key_matches(&key, entry.key()))?;
+        match entry {
+            Some(entry) => self.set_value(&entry, &value),
+            None => self.push(block, GValue::Pair(Box::new(key), Box::new(value))),
+        }
+    }
+    /// Sets the statement of the edge `from -> to`, which is also matched
+    /// when written `to <- from`. Hops of a chain `A -> B -> C` are not
+    /// matched, as they share one statement.
+    pub fn set_edge<K: Key + ?Sized + fmt::Display>(
+        &mut self,
+        block: &str,
+        from: &K,
+        to: &K,
+        stmt: GValue,
+    ) -> Result<(), Error> {
+        check_value(&stmt)?;
+        match self.find_edge(block, from, to)? {
+            Some(entry) => self.set_value(&entry, &stmt),
+            None => Err(missing_key(&format!("{} -> {}", from, to))),
+        }
+    }
+    /// Sets the statement of the edge named `id`.
+    pub fn set_edge_by_id(&mut self, block: &str, id: &str, stmt: GValue) -> Result<(), Error> {
+        check_value(&stmt)?;
+        match self.find_named_edge(block, id)? {
+            Some(entry) => self.set_value(&entry, &stmt),
+            None => Err(missing_key(id)),
+        }
+    }
+    fn set_value(&mut self, entry: &cst::Entry, value: &GValue) -> Result<(), Error> {
+        let text = value.to_string();
+        match entry.value() {
+            Some(old) => self.splice(old.syntax().text_range(), &text),
+            None => {
+                let target = entry
+                    .syntax()
+                    .children()
+                    .find(|node| matches!(node.kind(), SyntaxKind::Key | SyntaxKind::Edge))
+                    .unwrap();
+                let operator = match target.kind() {
+                    SyntaxKind::Edge => ":",
+                    _ => "=",
+                };
+                let end = target.text_range().end;
+                self.splice(end..end, &format!(" {} {}", operator, text))
+            }
+        }
+    }
+    fn find_edge<K: Key + ?Sized>(
+        &self,
+        block: &str,
+        from: &K,
+        to: &K,
+    ) -> Result<Option<cst::Entry>, Error> {
+        self.find_entry(block, |entry| {
+            entry.edge().is_some_and(|edge| {
+                let (from, to) = match edge.kind() {
+                    Some(EdgeKind::Reverse) => (to, from),
+                    _ => (from, to),
+                };
+                !edge.is_chain() && key_matches(from, edge.from()) && key_matches(to, edge.to())
+            })
+        })
+    }
+
+    fn find_named_edge(&self, block: &str, id: &str) -> Result<Option<cst::Entry>, Error> {
+        self.find_entry(block, |entry| key_matches(id, entry.edge_id()))
+    }
+
+    fn remove_entry(&mut self, entry: &cst::Entry) -> Result<(), Error> {
+        let range = entry.syntax().text_range();
+        let line_start = self.line_start(range.start);
+        let start = if self.src[line_start..range.start].trim().is_empty() {
+            line_start
+        } else {
+            range.start
+        };
+        let end = self.line_end(range.end);
+        self.splice(start..end, "")
+    }
+    /// Removes the definition `key = ...` or the bare entry `key`, with the
+    /// rest of its line.
+    pub fn remove<K: Key + ?Sized + fmt::Display>(
+        &mut self,
+        block: &str,
+        key: &K,
+    ) -> Result<(), Error> {
+        match self.find_entry(block, |entry| key_matches(key, entry.key()))? {
+            Some(entry) => self.remove_entry(&entry),
+            None => Err(missing_key(key)),
+        }
+    }
+    /// Removes the first edge `from -> to`.
+    pub fn remove_edge<K: Key + ?Sized + fmt::Display>(
+        &mut self,
+        block: &str,
+        from: &K,
+        to: &K,
+    ) -> Result<(), Error> {
+        match self.find_edge(block, from, to)? {
+            Some(entry) => self.remove_entry(&entry),
+            None => Err(missing_key(&format!("{} -> {}", from, to))),
+        }
+    }
+    /// Removes the edge named `id`.
+    pub fn remove_edge_by_id(&mut self, block: &str, id: &str) -> Result<(), Error> {
+        match self.find_named_edge(block, id)? {
+            Some(entry) => self.remove_entry(&entry),
+            None => Err(missing_key(id)),
+        }
+    }
+}
+
+impl FromStr for DocumentMut {
+    type Err = Error;
+
+    fn from_str(src: &str) -> Result<Self, Error> {
+        Self::parse(src)
+    }
+}
+
+impl fmt::Display for DocumentMut {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.src)
+    }
+}
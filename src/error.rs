@@ -0,0 +1,209 @@
+use crate::span::Span;
+use std::error;
+use std::fmt;
+use std::io;
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct Location {
+    pub file: Option<String>,
+    pub span: Span,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{}:", file)?;
+        }
+        write!(f, "{}:{}", self.span.line, self.span.column)
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    UnexpectedChar {
+        found: char,
+        location: Location,
+    },
+    UnterminatedString {
+        location: Location,
+    },
+    UnterminatedIdentifier {
+        location: Location,
+    },
+    InvalidNumber {
+        literal: String,
+        location: Location,
+    },
+    NumberOverflow {
+        literal: String,
+        location: Location,
+    },
+    InvalidEscape {
+        sequence: String,
+        location: Location,
+    },
+    UnexpectedToken {
+        expected: &'static str,
+        found: String,
+        location: Location,
+    },
+    UnexpectedEof {
+        expected: &'static str,
+        location: Location,
+    },
+    DuplicateKey {
+        key: String,
+        first: Span,
+        location: Location,
+    },
+    DuplicateBlock {
+        name: String,
+        first: Span,
+        location: Location,
+    },
+    DuplicateEdgeId {
+        id: String,
+        first: Span,
+        location: Location,
+    },
+    ReservedKeyword {
+        keyword: String,
+        location: Location,
+    },
+    MissingParentheses {
+        message: String,
+        message_span: Span,
+        location: Location,
+    },
+    Type {
+        expected: &'static str,
+        found: &'static str,
+    },
+    MissingKey {
+        key: String,
+    },
+    MissingBlock {
+        name: String,
+    },
+    IndexOutOfRange {
+        index: usize,
+        len: usize,
+    },
+    InvalidQuery {
+        query: String,
+        column: usize,
+        message: String,
+    },
+    Deserialize {
+        message: String,
+        location: Option<Location>,
+    },
+    Serialize {
+        message: String,
+    },
+}
+
+impl Error {
+    pub fn location(&self) -> Option<&Location> {
+        match self {
+            Error::Io(_)
+            | Error::Type { .. }
+            | Error::MissingKey { .. }
+            | Error::MissingBlock { .. }
+            | Error::IndexOutOfRange { .. }
+            | Error::InvalidQuery { .. }
+            | Error::Serialize { .. } => None,
+            Error::UnexpectedChar { location, .. }
+            | Error::UnterminatedString { location }
+            | Error::UnterminatedIdentifier { location }
+            | Error::InvalidNumber { location, .. }
+            | Error::NumberOverflow { location, .. }
+            | Error::InvalidEscape { location, .. }
+            | Error::UnexpectedToken { location, .. }
+            | Error::UnexpectedEof { location, .. }
+            | Error::DuplicateKey { location, .. }
+            | Error::DuplicateBlock { location, .. }
+            | Error::DuplicateEdgeId { location, .. }
+            | Error::ReservedKeyword { location, .. }
+            | Error::MissingParentheses { location, .. } => Some(location),
+            Error::Deserialize { location, .. } => location.as_ref(),
+        }
+    }
+    pub fn span(&self) -> Option<Span> {
+        self.location().map(|location| location.span)
+    }
+    pub fn message(&self) -> String {
+        match self {
+            Error::Io(err) => format!("io error: {}", err),
+            Error::UnexpectedChar { found, .. } => format!("unexpected character `{}`", found),
+            Error::UnterminatedString { .. } => "unterminated string".to_string(),
+            Error::UnterminatedIdentifier { .. } => "unterminated quoted identifier".to_string(),
+            Error::InvalidNumber { literal, .. } => format!("invalid number `{}`", literal),
+            Error::NumberOverflow { literal, .. } => {
+                format!("number `{}` is out of range", literal)
+            }
+            Error::InvalidEscape { sequence, .. } => {
+                format!("invalid escape sequence `{}`", sequence)
+            }
+            Error::UnexpectedToken {
+                expected, found, ..
+            } => format!("expected {}, found {}", expected, found),
+            Error::UnexpectedEof { expected, .. } => {
+                format!("expected {}, found end of file", expected)
+            }
+            Error::DuplicateKey { key, .. } => format!("duplicate key `{}`", key),
+            Error::DuplicateBlock { name, .. } => format!("duplicate block `[{}]`", name),
+            Error::DuplicateEdgeId { id, .. } => format!("duplicate edge id `{}`", id),
+            Error::ReservedKeyword { keyword, .. } => {
+                format!(
+                    "`{}` is a reserved keyword and can not be used as a name",
+                    keyword
+                )
+            }
+            Error::MissingParentheses { message, .. } => format!(
+                "arguments to message `{}` must be enclosed in parentheses",
+                message
+            ),
+            Error::Type { expected, found } => format!("expected {}, found {}", expected, found),
+            Error::MissingKey { key } => format!("no entry for key `{}`", key),
+            Error::MissingBlock { name } => format!("no block named `{}`", name),
+            Error::IndexOutOfRange { index, len } => {
+                format!("index {} is out of range for length {}", index, len)
+            }
+            Error::InvalidQuery {
+                query,
+                column,
+                message,
+            } => format!(
+                "invalid query `{}` at column {}: {}",
+                query, column, message
+            ),
+            Error::Deserialize { message, .. } | Error::Serialize { message } => message.clone(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.location() {
+            Some(location) => write!(f, "{}: {}", location, self.message()),
+            None => write!(f, "{}", self.message()),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
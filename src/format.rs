@@ -0,0 +1,277 @@
+//! Canonical formatting of GMML source.
+//!
+//! The formatter works on tokens so comments survive: comments are lexed
+//! as whitespace, and their text is recovered from the source. Entries get
+//! single spaces around `->`, `=` and the statement `:`, struct keys and
+//! edge ids are written `key: value`, and commas are followed by a single
+//! space. Structs and lists written over several lines keep one element per
+//! line, aligned after the opening bracket, so one holding a single element
+//! fits on one line. Runs of blank lines collapse into one, and blocks are
+//! separated by a blank line.
+
+use crate::error::Error;
+use crate::parse::{Scanner, Symbol, Token};
+use crate::span::Spanned;
+
+/// Formats `src`, which must be a valid GMML document.
+pub fn format_str(src: &str) -> Result<String, Error> {
+    crate::parse_str(src)?;
+    let toks = Scanner::from_str(src).tokenize()?;
+    Ok(Formatter::new(src, &toks).format())
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+enum Item<'a> {
+    Token(&'a Token, &'a str),
+    Comment(&'a str),
+    Newline,
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+enum Line {
+    Empty,
+    Header,
+    Entry,
+    Comment,
+}
+
+struct Group {
+    multiline: bool,
+    align: usize,
+}
+
+struct Formatter<'a> {
+    items: Vec<Item<'a>>,
+    /// Whether the group opened at each item spans several lines.
+    multiline: Vec<bool>,
+    out: String,
+    column: usize,
+    groups: Vec<Group>,
+    prev: Option<&'a Token>,
+    line_start: bool,
+    line: Line,
+    blank: bool,
+    needs_break: bool,
+    /// The number of tokens written for the current entry.
+    entry_tokens: usize,
+}
+
+fn is_open(tok: &Token) -> bool {
+    matches!(
+        tok,
+        Token::Symbol(Symbol::LeftParen)
+            | Token::Symbol(Symbol::LeftBrace)
+            | Token::Symbol(Symbol::LeftBracket)
+    )
+}
+
+fn is_close(tok: &Token) -> bool {
+    matches!(
+        tok,
+        Token::Symbol(Symbol::RightParen)
+            | Token::Symbol(Symbol::RightBrace)
+            | Token::Symbol(Symbol::RightBracket)
+    )
+}
+
+fn is_operator(tok: &Token) -> bool {
+    matches!(
+        tok,
+        Token::Arrow(_)
+            | Token::Symbol(Symbol::Plus)
+            | Token::Symbol(Symbol::Equal)
+            | Token::Symbol(Symbol::Colon)
+            | Token::Symbol(Symbol::Comma)
+    )
+}
+
+impl<'a> Formatter<'a> {
+    fn new(src: &'a str, toks: &'a [Spanned<Token>]) -> Self {
+        let mut items = Vec::new();
+        for tok in toks {
+            let text = &src[tok.span.start..tok.span.end];
+            items.push(match &tok.node {
+                Token::Newline => Item::Newline,
+                Token::Whitespace if text.starts_with(';') => Item::Comment(text.trim_end()),
+                Token::Whitespace => continue,
+                node => Item::Token(node, text),
+            });
+        }
+        let mut multiline = vec![false; items.len()];
+        let mut open = Vec::new();
+        for (i, item) in items.iter().enumerate() {
+            match item {
+                Item::Token(tok, _) if is_open(tok) => open.push(i),
+                Item::Token(tok, _) if is_close(tok) => {
+                    open.pop();
+                }
+                Item::Newline | Item::Comment(_) => {
+                    for &i in &open {
+                        multiline[i] = true;
+                    }
+                }
+                _ => {}
+            }
+        }
+        Self {
+            items,
+            multiline,
+            out: String::new(),
+            column: 0,
+            groups: Vec::new(),
+            prev: None,
+            line_start: true,
+            line: Line::Empty,
+            blank: false,
+            needs_break: false,
+            entry_tokens: 0,
+        }
+    }
+
+    fn push(&mut self, text: &str) {
+        self.out.push_str(text);
+        match text.rfind('\n') {
+            Some(i) => self.column = text[i + 1..].chars().count(),
+            None => self.column += text.chars().count(),
+        }
+    }
+
+    fn indent(&self) -> usize {
+        self.groups.last().map_or(0, |group| group.align)
+    }
+
+    fn new_line(&mut self) {
+        let indent = self.indent();
+        self.push("\n");
+        self.push(&" ".repeat(indent));
+    }
+
+    fn end_line(&mut self) {
+        if !self.line_start {
+            self.push("\n");
+            self.line_start = true;
+        } else if self.line != Line::Empty {
+            self.blank = true;
+        }
+    }
+
+    /// The next item that is not a newline, if any.
+    fn lookahead(&self, i: usize) -> Option<&Item<'a>> {
+        self.items[i + 1..]
+            .iter()
+            .find(|item| **item != Item::Newline)
+    }
+
+    fn start_line(&mut self, header: bool) {
+        if self.line != Line::Empty && (self.blank || header && self.line != Line::Comment) {
+            self.push("\n");
+        }
+        let indent = self.indent();
+        self.push(&" ".repeat(indent));
+        self.line_start = false;
+        self.blank = false;
+    }
+
+    fn separator(&mut self, tok: &Token) {
+        let prev = match self.prev {
+            Some(prev) => prev,
+            None => return,
+        };
+        let depth = self.groups.len();
+        let multiline = self.groups.last().is_some_and(|group| group.multiline);
+        match (prev, tok) {
+            (_, Token::Symbol(Symbol::Comma)) => {}
+            (_, tok) if is_close(tok) => {}
+            (prev, _) if is_open(prev) => {}
+            (Token::Identifier(_), Token::Symbol(Symbol::LeftParen)) => {}
+            // a dotted path, `Router.eth0`
+            (Token::Symbol(Symbol::Point), _) | (_, Token::Symbol(Symbol::Point)) => {}
+            (_, Token::Symbol(Symbol::Colon)) if depth > 0 => {}
+            // the id of a named edge, `e1: A -> B`
+            (_, Token::Symbol(Symbol::Colon)) if self.entry_tokens == 1 => {}
+            (Token::Symbol(Symbol::Comma), _) if multiline => self.new_line(),
+            _ => self.push(" "),
+        }
+    }
+
+    fn token(&mut self, i: usize, tok: &'a Token, text: &str) {
+        if let Token::Symbol(Symbol::Comma) = tok {
+            if let Some(Item::Token(next, _)) = self.lookahead(i) {
+                if is_close(next) {
+                    return;
+                }
+            }
+        }
+        if self.line_start {
+            let header = self.groups.is_empty() && *tok == Token::Symbol(Symbol::LeftBracket);
+            self.start_line(header);
+            self.entry_tokens = 0;
+        } else if self.needs_break {
+            self.new_line();
+        } else {
+            self.separator(tok);
+        }
+        self.needs_break = false;
+        self.push(text);
+        self.entry_tokens += 1;
+        if is_open(tok) {
+            self.groups.push(Group {
+                multiline: self.multiline[i],
+                align: self.column,
+            });
+        } else if is_close(tok) {
+            self.groups.pop();
+        }
+        self.prev = Some(tok);
+        if self.groups.is_empty() {
+            self.line = match tok {
+                Token::Symbol(Symbol::RightBracket) => Line::Header,
+                _ => Line::Entry,
+            };
+        }
+    }
+
+    fn format(mut self) -> String {
+        for i in 0..self.items.len() {
+            match self.items[i] {
+                Item::Token(tok, text) => self.token(i, tok, text),
+                Item::Comment(text) => {
+                    if self.line_start {
+                        self.start_line(false);
+                        if self.groups.is_empty() {
+                            self.line = Line::Comment;
+                        }
+                    } else {
+                        if self.needs_break {
+                            self.new_line();
+                        } else {
+                            self.push(" ");
+                        }
+                        if self.prev.is_some_and(is_operator) {
+                            self.needs_break = true;
+                        }
+                    }
+                    self.push(text);
+                    if !self.groups.is_empty() {
+                        self.needs_break = true;
+                    }
+                }
+                Item::Newline if !self.groups.is_empty() => {}
+                Item::Newline => {
+                    let continued = self.prev.is_some_and(is_operator)
+                        || matches!(self.lookahead(i), Some(Item::Token(Token::Arrow(_), _)));
+                    if self.line_start || !continued {
+                        self.end_line();
+                    } else if matches!(self.items[i - 1], Item::Comment(_)) {
+                        // joining would move the rest of the entry into the comment
+                        self.needs_break = true;
+                    }
+                }
+            }
+        }
+        if !self.line_start {
+            self.out.push('\n');
+        }
+        self.out
+    }
+}
@@ -1 +1,72 @@
+pub mod cst;
+pub mod de;
+pub mod diagnostic;
+pub mod document;
+pub mod edit;
+pub mod error;
+pub mod format;
 pub mod parse;
+pub mod query;
+pub mod ser;
+pub mod span;
+pub mod value;
+
+pub use de::{from_document, from_str, from_value};
+pub use diagnostic::Diagnostic;
+pub use document::{Block, Document, DuplicateBlocks};
+pub use edit::DocumentMut;
+pub use error::{Error, Location};
+pub use query::{query, Query};
+pub use ser::{to_document, to_string, to_value};
+pub use span::{Span, Spanned};
+pub use value::{EdgeKind, EdgeRef, GValue, HyperEdgeRef, Key, Map};
+
+use parse::{Parser, Scanner};
+use std::io::Read;
+
+/// Parses a GMML model held in memory.
+pub fn parse_str(src: &str) -> Result<Document, Error> {
+    parse_scanner(Scanner::from_str(src))
+}
+
+/// Parses a GMML model from any reader, e.g. a file or stdin.
+pub fn parse_reader<R: Read>(reader: R) -> Result<Document, Error> {
+    parse_scanner(Scanner::from_reader(reader)?)
+}
+
+/// Parses a GMML file, reporting errors against its file name.
+pub fn parse_file(filename: &str) -> Result<Document, Error> {
+    parse_scanner(Scanner::new(filename.to_string())?)
+}
+
+/// Parses the tokens produced by `scanner`.
+pub fn parse_scanner(scanner: Scanner) -> Result<Document, Error> {
+    let filename = scanner.filename().map(str::to_string);
+    let end = scanner.end_span();
+    let mut parser = Parser::new(scanner.tokenize()?).with_end_span(end);
+    if let Some(filename) = &filename {
+        parser = parser.with_filename(filename);
+    }
+    parser.parse()
+}
+
+/// Parses a GMML model held in memory, reporting every error found instead
+/// of stopping at the first one.
+pub fn parse_str_recovering(src: &str) -> (Document, Vec<Error>) {
+    parse_scanner_recovering(Scanner::from_str(src))
+}
+
+/// Parses the tokens produced by `scanner` in recovering mode.
+pub fn parse_scanner_recovering(scanner: Scanner) -> (Document, Vec<Error>) {
+    let filename = scanner.filename().map(str::to_string);
+    let end = scanner.end_span();
+    let (toks, mut errors) = scanner.tokenize_recovering();
+    let mut parser = Parser::new(toks).with_end_span(end);
+    if let Some(filename) = &filename {
+        parser = parser.with_filename(filename);
+    }
+    let (root, parse_errors) = parser.parse_recovering();
+    errors.extend(parse_errors);
+    errors.sort_by_key(|err| err.span().map(|span| span.start));
+    (root, errors)
+}
@@ -1,8 +1,13 @@
-use std::collections::HashMap;
+use crate::document::{Block, Document, DuplicateBlocks};
+use crate::error::{Error, Location};
+use crate::span::{Span, Spanned};
+use crate::value::{EdgeKind, Key};
+pub use crate::value::{GValue, Map};
 use std::convert::From;
 use std::fs::File;
 use std::io;
 use std::io::prelude::*;
+use std::slice;
 
 #[derive(PartialEq, Debug, Clone)]
 pub enum Symbol {
@@ -20,216 +25,570 @@ pub enum Symbol {
     Lt,
     Bt,
     Equal,
+    Plus,
 }
 
 #[derive(PartialEq, Debug, Clone)]
 pub enum Token {
     Identifier(String),
-    Number(f64),
+    Integer(i64),
+    Float(f64),
     String(String),
+    Bool(bool),
+    Null,
     Symbol(Symbol),
-    Arrow,
+    Arrow(EdgeKind),
     Newline,
     Whitespace,
+    Error,
+}
+
+impl Token {
+    pub fn describe(&self) -> String {
+        match self {
+            Token::Identifier(name) => format!("identifier `{}`", name),
+            Token::Integer(number) => format!("number `{}`", number),
+            Token::Float(number) => format!("number `{:?}`", number),
+            Token::String(string) => format!("string {:?}", string),
+            Token::Bool(value) => format!("`{}`", value),
+            Token::Null => "`none`".to_string(),
+            Token::Symbol(symbol) => format!("`{}`", symbol.as_str()),
+            Token::Arrow(kind) => format!("`{}`", kind),
+            Token::Newline => "newline".to_string(),
+            Token::Whitespace => "whitespace".to_string(),
+            Token::Error => "invalid token".to_string(),
+        }
+    }
+    fn starts_value(&self) -> bool {
+        matches!(
+            self,
+            Token::Identifier(_)
+                | Token::Integer(_)
+                | Token::Float(_)
+                | Token::String(_)
+                | Token::Bool(_)
+                | Token::Null
+                | Token::Symbol(Symbol::LeftBrace)
+        )
+    }
+}
+
+impl Symbol {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Symbol::LeftBracket => "[",
+            Symbol::RightBracket => "]",
+            Symbol::LeftParen => "(",
+            Symbol::RightParen => ")",
+            Symbol::LeftBrace => "{",
+            Symbol::RightBrace => "}",
+            Symbol::Comma => ",",
+            Symbol::Point => ".",
+            Symbol::Semicolon => ";",
+            Symbol::Colon => ":",
+            Symbol::Minus => "-",
+            Symbol::Lt => ">",
+            Symbol::Bt => "<",
+            Symbol::Equal => "=",
+            Symbol::Plus => "+",
+        }
+    }
 }
 
 #[derive(Clone)]
 pub struct Scanner {
-    filename: String,
+    filename: Option<String>,
     buf: Vec<char>,
     pos: usize,
-    sym: Vec<String>,
+    offset: usize,
+    line: usize,
+    column: usize,
 }
 
 impl Scanner {
-    pub fn new(filename: String) -> Self {
-        let buf = open(&filename).expect("error when reading the file");
+    pub fn new(filename: String) -> io::Result<Self> {
+        let buf = open(&filename)?;
+        let mut scanner = Self::from_str(&buf);
+        scanner.filename = Some(filename);
+        Ok(scanner)
+    }
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(src: &str) -> Self {
         Self {
-            filename: filename,
-            buf: buf.chars().collect(),
+            filename: None,
+            buf: src.chars().collect(),
             pos: 0,
-            sym: Vec::new(),
+            offset: 0,
+            line: 1,
+            column: 1,
         }
     }
-    fn lex_string(&mut self) -> Option<(Token, usize)> {
-        let start = self.pos + 1;
-        let mut end = self.pos + 1;
-        while let Some(&c) = self.buf.get(end) {
-            if c == '"' {
-                break;
-            } else {
-                end += 1;
+    pub fn from_reader<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut contents = String::new();
+        reader.read_to_string(&mut contents)?;
+        Ok(Self::from_str(&contents))
+    }
+    pub fn with_filename(mut self, filename: &str) -> Self {
+        self.filename = Some(filename.to_string());
+        self
+    }
+    pub fn filename(&self) -> Option<&str> {
+        self.filename.as_deref()
+    }
+    /// The empty span at the end of the input, with its column counted in
+    /// chars.
+    pub fn end_span(&self) -> Span {
+        let line_start = self
+            .buf
+            .iter()
+            .rposition(|c| *c == '\n')
+            .map_or(0, |i| i + 1);
+        let line = 1 + self.buf.iter().filter(|c| **c == '\n').count();
+        let offset = self.buf.iter().map(|c| c.len_utf8()).sum();
+        Span::new(offset, offset, line, self.buf.len() - line_start + 1)
+    }
+    pub fn tokenize(self) -> Result<Vec<Spanned<Token>>, Error> {
+        self.collect()
+    }
+    /// Tokenizes the whole input, replacing malformed tokens with
+    /// `Token::Error` and returning their errors alongside the tokens.
+    pub fn tokenize_recovering(self) -> (Vec<Spanned<Token>>, Vec<Error>) {
+        let mut toks = Vec::new();
+        let mut errors = Vec::new();
+        for tok in self {
+            match tok {
+                Ok(tok) => toks.push(tok),
+                Err(err) => {
+                    let span = err.span().unwrap_or_default();
+                    toks.push(Spanned::new(Token::Error, span));
+                    errors.push(err);
+                }
             }
         }
-        if end > self.buf.len() {
-            None
+        (toks, errors)
+    }
+    fn peek(&self) -> Option<char> {
+        self.buf.get(self.pos).copied()
+    }
+    fn at_newline(&self) -> bool {
+        match self.peek() {
+            Some('\n') => true,
+            Some('\r') => self.buf.get(self.pos + 1) == Some(&'\n'),
+            _ => false,
+        }
+    }
+    fn bump_newline(&mut self) {
+        if self.peek() == Some('\r') {
+            self.bump();
+        }
+        self.bump();
+    }
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        self.offset += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
         } else {
-            let range = start..end;
-            Some((
-                Token::String(self.buf[range].iter().collect()),
-                end - start + 2,
-            ))
-        }
-    }
-    fn lex_number(&mut self) -> Option<(Token, usize)> {
-        let start = self.pos;
-        let mut end = self.pos;
-        let mut point_count = 0;
-        while self.pos < self.buf.len() {
-            match self.buf.get(end) {
-                Some('.') => {
-                    if point_count > 1 {
-                        break;
-                    } else {
-                        point_count += 1;
-                    }
-                    end += 1;
-                }
-                Some(c) if c.is_numeric() => {
-                    end += 1;
-                }
-                _ => {
+            self.column += 1;
+        }
+        Some(c)
+    }
+    fn span_from(&self, start: (usize, usize, usize)) -> Span {
+        let (offset, line, column) = start;
+        Span::new(offset, self.offset, line, column)
+    }
+    fn location(&self, span: Span) -> Location {
+        Location {
+            file: self.filename.clone(),
+            span,
+        }
+    }
+    fn mark(&self) -> (usize, usize, usize) {
+        (self.offset, self.line, self.column)
+    }
+    fn starts_with(&self, pattern: &str) -> bool {
+        pattern
+            .chars()
+            .enumerate()
+            .all(|(i, c)| self.buf.get(self.pos + i) == Some(&c))
+    }
+    fn unterminated(&self, start: (usize, usize, usize)) -> Error {
+        Error::UnterminatedString {
+            location: self.location(self.span_from(start)),
+        }
+    }
+    fn lex_string(&mut self, start: (usize, usize, usize)) -> Result<Token, Error> {
+        if self.starts_with("\"\"\"") {
+            return self.lex_multiline_string(start);
+        }
+        self.bump();
+        let mut string = String::new();
+        let mut error = None;
+        loop {
+            match self.peek() {
+                Some('"') => {
+                    self.bump();
                     break;
                 }
+                Some('\\') => match self.lex_escape() {
+                    Ok(c) => string.push(c),
+                    Err(err) => {
+                        error.get_or_insert(err);
+                    }
+                },
+                Some(_) if self.at_newline() => return Err(self.unterminated(start)),
+                Some(c) => {
+                    self.bump();
+                    string.push(c);
+                }
+                None => return Err(self.unterminated(start)),
             }
         }
-        if end >= self.buf.len() {
-            None
-        } else {
-            let range = start..end;
-            let tmp: String = self.buf[range].iter().collect();
-            Some((Token::Number(tmp.parse::<f64>().unwrap()), end - start))
+        match error {
+            Some(err) => Err(err),
+            None => Ok(Token::String(string)),
         }
     }
-    fn lex_identifier(&mut self) -> Option<(Token, usize)> {
-        let start = self.pos;
-        let mut end = self.pos;
-        while self.pos < self.buf.len() {
-            match self.buf.get(end) {
-                Some(c) if c.is_alphabetic() || c.is_numeric() || c == &'_' => {
-                    end += 1;
+    fn lex_multiline_string(&mut self, start: (usize, usize, usize)) -> Result<Token, Error> {
+        for _ in 0..3 {
+            self.bump();
+        }
+        // a newline right after the opening quotes is not part of the string
+        if self.at_newline() {
+            self.bump_newline();
+        }
+        let mut string = String::new();
+        let mut error = None;
+        loop {
+            if self.starts_with("\"\"\"") {
+                for _ in 0..3 {
+                    self.bump();
                 }
-                _ => {
-                    break;
+                break;
+            }
+            match self.peek() {
+                Some('\\') => match self.lex_escape() {
+                    Ok(c) => string.push(c),
+                    Err(err) => {
+                        error.get_or_insert(err);
+                    }
+                },
+                Some(_) if self.at_newline() => {
+                    self.bump_newline();
+                    string.push('\n');
                 }
+                Some(c) => {
+                    self.bump();
+                    string.push(c);
+                }
+                None => return Err(self.unterminated(start)),
             }
         }
-        if end >= self.buf.len() {
-            None
-        } else {
-            let range = start..end;
-            Some((
-                Token::Identifier(self.buf[range].iter().collect()),
-                end - start,
-            ))
+        match error {
+            Some(err) => Err(err),
+            None => Ok(Token::String(string)),
         }
     }
-}
-
-impl Iterator for Scanner {
-    type Item = Token;
-
-    fn next(&mut self) -> Option<Token> {
-        if self.pos >= self.buf.len() {
-            return None;
+    fn at_raw_string(&self) -> bool {
+        let mut i = self.pos + 1;
+        while self.buf.get(i) == Some(&'#') {
+            i += 1;
+        }
+        self.peek() == Some('r') && self.buf.get(i) == Some(&'"')
+    }
+    fn lex_raw_string(&mut self, start: (usize, usize, usize)) -> Result<Token, Error> {
+        self.bump();
+        let mut hashes = 0;
+        while self.peek() == Some('#') {
+            self.bump();
+            hashes += 1;
+        }
+        self.bump();
+        let terminator: String = std::iter::once('"')
+            .chain(std::iter::repeat_n('#', hashes))
+            .collect();
+        let mut string = String::new();
+        loop {
+            if self.starts_with(&terminator) {
+                for _ in 0..=hashes {
+                    self.bump();
+                }
+                return Ok(Token::String(string));
+            }
+            match self.bump() {
+                Some(c) => string.push(c),
+                None => return Err(self.unterminated(start)),
+            }
         }
-        let c = self.buf.get(self.pos).unwrap().clone();
+    }
+    fn lex_escape(&mut self) -> Result<char, Error> {
+        let start = (self.pos, self.mark());
+        self.bump();
+        let c = match self.peek() {
+            Some(c) if !self.at_newline() => c,
+            _ => return Err(self.invalid_escape(start)),
+        };
+        self.bump();
         match c {
-            ' ' | '\t' => {
-                self.pos += 1;
-                while self.pos < self.buf.len() {
-                    let d = self.buf.get(self.pos).unwrap();
-                    if d == &' ' || d == &'\t' {
-                        self.pos += 1;
-                    } else {
+            '"' => Ok('"'),
+            '\\' => Ok('\\'),
+            'n' => Ok('\n'),
+            'r' => Ok('\r'),
+            't' => Ok('\t'),
+            '0' => Ok('\0'),
+            'u' => {
+                if self.peek() != Some('{') {
+                    return Err(self.invalid_escape(start));
+                }
+                self.bump();
+                let mut digits = String::new();
+                while let Some(c) = self.peek() {
+                    if c == '}' || c == '"' || self.at_newline() {
                         break;
                     }
+                    digits.push(c);
+                    self.bump();
                 }
-                Some(Token::Whitespace)
-            }
-            '"' => {
-                let (tok, len) = self.lex_string().expect("parsing error: string start");
-                self.pos += len;
-                Some(tok)
-            }
-            ':' => {
-                self.pos += 1;
-                Some(Token::Symbol(Symbol::Colon))
-            }
-            ';' => {
-                while self.pos < self.buf.len() && self.buf.get(self.pos).unwrap() != &'\n' {
-                    self.pos += 1;
+                if self.peek() != Some('}') {
+                    return Err(self.invalid_escape(start));
                 }
-                Some(Token::Whitespace)
-            }
-            ',' => {
-                self.pos += 1;
-                Some(Token::Symbol(Symbol::Comma))
-            }
-            '.' => {
-                self.pos += 1;
-                Some(Token::Symbol(Symbol::Point))
+                self.bump();
+                if digits.is_empty() || digits.len() > 6 {
+                    return Err(self.invalid_escape(start));
+                }
+                u32::from_str_radix(&digits, 16)
+                    .ok()
+                    .and_then(std::char::from_u32)
+                    .ok_or_else(|| self.invalid_escape(start))
             }
-            '(' => {
-                self.pos += 1;
-                Some(Token::Symbol(Symbol::LeftParen))
+            _ => Err(self.invalid_escape(start)),
+        }
+    }
+    fn invalid_escape(&self, start: (usize, (usize, usize, usize))) -> Error {
+        let (pos, mark) = start;
+        Error::InvalidEscape {
+            sequence: self.buf[pos..self.pos].iter().collect(),
+            location: self.location(self.span_from(mark)),
+        }
+    }
+    fn lex_number(&mut self, start: (usize, usize, usize)) -> Result<Token, Error> {
+        let mut literal = String::new();
+        if self.peek() == Some('-') {
+            literal.push('-');
+            self.bump();
+        }
+        let radix_prefix =
+            self.starts_with("0x") || self.starts_with("0o") || self.starts_with("0b");
+        while let Some(c) = self.peek() {
+            let exponent_sign =
+                (c == '-' || c == '+') && !radix_prefix && literal.ends_with(['e', 'E']);
+            if c.is_alphanumeric() || c == '.' || c == '_' || exponent_sign {
+                literal.push(c);
+                self.bump();
+            } else {
+                break;
             }
-            ')' => {
-                self.pos += 1;
-                Some(Token::Symbol(Symbol::RightParen))
+        }
+        let location = self.location(self.span_from(start));
+        match parse_number(&literal) {
+            Ok(tok) => Ok(tok),
+            Err(NumberError::Invalid) => Err(Error::InvalidNumber { literal, location }),
+            Err(NumberError::Overflow) => Err(Error::NumberOverflow { literal, location }),
+        }
+    }
+    fn lex_identifier(&mut self) -> Result<Token, Error> {
+        let mut name = String::new();
+        while let Some(c) = self.peek() {
+            if c.is_alphabetic() || c.is_numeric() || c == '_' {
+                name.push(c);
+                self.bump();
+            } else {
+                break;
             }
-            '[' => {
-                self.pos += 1;
-                Some(Token::Symbol(Symbol::LeftBracket))
+        }
+        Ok(match name.as_str() {
+            "true" => Token::Bool(true),
+            "false" => Token::Bool(false),
+            "none" => Token::Null,
+            _ => Token::Identifier(name),
+        })
+    }
+    fn lex_quoted_identifier(&mut self, start: (usize, usize, usize)) -> Result<Token, Error> {
+        self.bump();
+        let mut name = String::new();
+        loop {
+            match self.peek() {
+                Some('`') => {
+                    self.bump();
+                    return Ok(Token::Identifier(name));
+                }
+                Some(_) if self.at_newline() => break,
+                Some(c) => {
+                    self.bump();
+                    name.push(c);
+                }
+                None => break,
             }
-            ']' => {
-                self.pos += 1;
-                Some(Token::Symbol(Symbol::RightBracket))
+        }
+        Err(Error::UnterminatedIdentifier {
+            location: self.location(self.span_from(start)),
+        })
+    }
+    fn lex_symbol(&mut self, symbol: Symbol) -> Result<Token, Error> {
+        self.bump();
+        Ok(Token::Symbol(symbol))
+    }
+}
+
+impl Iterator for Scanner {
+    type Item = Result<Spanned<Token>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let c = self.peek()?;
+        let start = self.mark();
+        let tok = match c {
+            ' ' | '\t' => {
+                while let Some(' ') | Some('\t') = self.peek() {
+                    self.bump();
+                }
+                Ok(Token::Whitespace)
             }
-            '{' => {
-                self.pos += 1;
-                Some(Token::Symbol(Symbol::LeftBrace))
+            '"' => self.lex_string(start),
+            ':' => self.lex_symbol(Symbol::Colon),
+            ';' => {
+                while self.peek().is_some() && !self.at_newline() {
+                    self.bump();
+                }
+                Ok(Token::Whitespace)
             }
-            '}' => {
-                self.pos += 1;
-                Some(Token::Symbol(Symbol::RightBrace))
+            ',' => self.lex_symbol(Symbol::Comma),
+            '.' => self.lex_symbol(Symbol::Point),
+            '(' => self.lex_symbol(Symbol::LeftParen),
+            ')' => self.lex_symbol(Symbol::RightParen),
+            '[' => self.lex_symbol(Symbol::LeftBracket),
+            ']' => self.lex_symbol(Symbol::RightBracket),
+            '{' => self.lex_symbol(Symbol::LeftBrace),
+            '}' => self.lex_symbol(Symbol::RightBrace),
+            '-' if self
+                .buf
+                .get(self.pos + 1)
+                .is_some_and(|c| c.is_ascii_digit()) =>
+            {
+                self.lex_number(start)
             }
             '-' => {
-                self.pos += 1;
-                if self.pos < self.buf.len() && self.buf[self.pos] == '>' {
-                    self.pos += 1;
-                    Some(Token::Arrow)
-                } else {
-                    Some(Token::Symbol(Symbol::Minus))
+                self.bump();
+                match self.peek() {
+                    Some('>') => {
+                        self.bump();
+                        Ok(Token::Arrow(EdgeKind::Directed))
+                    }
+                    Some('-') => {
+                        self.bump();
+                        Ok(Token::Arrow(EdgeKind::Undirected))
+                    }
+                    _ => Ok(Token::Symbol(Symbol::Minus)),
                 }
             }
-            '>' => {
-                self.pos += 1;
-                Some(Token::Symbol(Symbol::Lt))
-            }
-            '=' => {
-                self.pos += 1;
-                Some(Token::Symbol(Symbol::Equal))
+            '<' if self.buf.get(self.pos + 1) == Some(&'-') => {
+                self.bump();
+                self.bump();
+                if self.peek() == Some('>') {
+                    self.bump();
+                    Ok(Token::Arrow(EdgeKind::Bidirectional))
+                } else {
+                    Ok(Token::Arrow(EdgeKind::Reverse))
+                }
             }
+            '<' => self.lex_symbol(Symbol::Bt),
+            '>' => self.lex_symbol(Symbol::Lt),
+            '=' => self.lex_symbol(Symbol::Equal),
+            '+' => self.lex_symbol(Symbol::Plus),
             '\n' => {
-                self.pos += 1;
-                Some(Token::Newline)
-            }
-            c if c.is_alphabetic() => {
-                let (tok, len) = self.lex_identifier().expect("parsing error: identifier");
-                self.pos += len;
-                Some(tok)
+                self.bump();
+                Ok(Token::Newline)
             }
-            c if c.is_numeric() => {
-                let (tok, len) = self.lex_number().expect("parsing error: number");
-                self.pos += len;
-                Some(tok)
+            '\r' if self.at_newline() => {
+                self.bump_newline();
+                Ok(Token::Newline)
             }
+            'r' if self.at_raw_string() => self.lex_raw_string(start),
+            '`' => self.lex_quoted_identifier(start),
+            c if c.is_alphabetic() => self.lex_identifier(),
+            c if c.is_numeric() => self.lex_number(start),
             c => {
-                println!("unmatch: {}", c);
-                None
+                self.bump();
+                Err(Error::UnexpectedChar {
+                    found: c,
+                    location: self.location(self.span_from(start)),
+                })
             }
+        };
+        Some(tok.map(|tok| Spanned::new(tok, self.span_from(start))))
+    }
+}
+
+enum NumberError {
+    Invalid,
+    Overflow,
+}
+
+fn is_digits(digits: &str, radix: u32) -> bool {
+    digits.starts_with(|c: char| c.is_digit(radix))
+        && digits.ends_with(|c: char| c.is_digit(radix))
+        && digits.chars().all(|c| c.is_digit(radix) || c == '_')
+}
+
+fn parse_number(literal: &str) -> Result<Token, NumberError> {
+    let (sign, body) = match literal.strip_prefix('-') {
+        Some(body) => ("-", body),
+        None => ("", literal),
+    };
+    let radix = match body.get(..2) {
+        Some("0x") => 16,
+        Some("0o") => 8,
+        Some("0b") => 2,
+        _ => 10,
+    };
+    if radix != 10 {
+        let digits = &body[2..];
+        if !is_digits(digits, radix) {
+            return Err(NumberError::Invalid);
+        }
+        let digits = format!("{}{}", sign, digits.replace('_', ""));
+        return i64::from_str_radix(&digits, radix)
+            .map(Token::Integer)
+            .map_err(|_| NumberError::Overflow);
+    }
+    let (mantissa, exponent) = match body.find(['e', 'E']) {
+        Some(i) => (&body[..i], Some(&body[i + 1..])),
+        None => (body, None),
+    };
+    let (integer, fraction) = match mantissa.find('.') {
+        Some(i) => (&mantissa[..i], Some(&mantissa[i + 1..])),
+        None => (mantissa, None),
+    };
+    let exponent_digits = exponent.map(|e| e.strip_prefix(['+', '-']).unwrap_or(e));
+    if !is_digits(integer, 10)
+        || !fraction.is_none_or(|f| is_digits(f, 10))
+        || !exponent_digits.is_none_or(|e| is_digits(e, 10))
+    {
+        return Err(NumberError::Invalid);
+    }
+    let clean = format!("{}{}", sign, body.replace('_', ""));
+    if fraction.is_some() || exponent.is_some() {
+        match clean.parse::<f64>() {
+            Ok(float) if float.is_finite() => Ok(Token::Float(float)),
+            Ok(_) => Err(NumberError::Overflow),
+            Err(_) => Err(NumberError::Invalid),
         }
+    } else {
+        clean
+            .parse::<i64>()
+            .map(Token::Integer)
+            .map_err(|_| NumberError::Overflow)
     }
 }
 
@@ -242,293 +601,720 @@ fn open(path: &str) -> io::Result<String> {
 
 #[derive(Debug, Clone)]
 pub enum AST {
-    Block { name: String, content: Vec<AST> },
-    LeafDef { target: Box<AST>, stmt: Box<AST> },
-    Edge { from: Box<AST>, to: Box<AST> },
-    EdgeDef { target: Box<AST>, stmt: Box<AST> },
-    Struct(Vec<AST>),
-    Message { name: String, args: Vec<AST> },
+    Block {
+        name: Spanned<String>,
+        content: Vec<Spanned<AST>>,
+    },
+    LeafDef {
+        target: Box<Spanned<AST>>,
+        stmt: Box<Spanned<AST>>,
+    },
+    /// An edge `A -> B`. As in `GValue`, `from` and `to` follow the
+    /// direction of the edge.
+    Edge {
+        from: Box<Spanned<AST>>,
+        to: Box<Spanned<AST>>,
+        kind: EdgeKind,
+    },
+    EdgeDef {
+        target: Box<Spanned<AST>>,
+        stmt: Box<Spanned<AST>>,
+    },
+    /// An edge or edge definition with an id, `e1: Y -> Z : Attack()`.
+    Named {
+        id: Spanned<String>,
+        edge: Box<Spanned<AST>>,
+    },
+    /// An edge joining several nodes, `X + Y -> Z`. As in `GValue`,
+    /// `sources` and `targets` follow the direction of the edge.
+    HyperEdge {
+        sources: Vec<Spanned<AST>>,
+        targets: Vec<Spanned<AST>>,
+        kind: EdgeKind,
+    },
+    /// The nodes `X + Y` on one side of a hyperedge.
+    Join(Vec<Spanned<AST>>),
+    /// The hops of a chain `A -> B -> C`, each an `Edge` or `EdgeDef` with
+    /// its own span. Chains are flattened into the entries of their block.
+    Chain(Vec<Spanned<AST>>),
+    Struct(Vec<Spanned<AST>>),
+    Message {
+        name: String,
+        args: Vec<Spanned<AST>>,
+    },
     String(String),
-    Number(f64),
+    Integer(i64),
+    Float(f64),
+    Bool(bool),
+    Null,
     Symbol(String),
-    List(Vec<AST>),
+    /// A dotted path `Router.eth0`.
+    Path(Vec<String>),
+    List(Vec<Spanned<AST>>),
+    Error,
+}
+
+/// The keys of an end of an edge, which may be a group.
+fn group_keys(endpoint: &Spanned<AST>) -> &[Spanned<AST>] {
+    match &endpoint.node {
+        AST::List(keys) => keys,
+        _ => slice::from_ref(endpoint),
+    }
+}
+
+/// The nodes of one side of an edge, which may be joined with `+`.
+fn joined_keys(endpoint: &Spanned<AST>) -> Vec<Spanned<AST>> {
+    match &endpoint.node {
+        AST::Join(keys) => keys.clone(),
+        _ => vec![endpoint.clone()],
+    }
+}
+
+/// Makes the edge `from -> to`, which is a hyperedge if either side joins
+/// several nodes.
+fn edge(from: &Spanned<AST>, to: &Spanned<AST>, kind: EdgeKind) -> AST {
+    if let (AST::Join(_), _) | (_, AST::Join(_)) = (&from.node, &to.node) {
+        let (sources, targets) = match kind {
+            EdgeKind::Reverse => (joined_keys(to), joined_keys(from)),
+            _ => (joined_keys(from), joined_keys(to)),
+        };
+        return AST::HyperEdge {
+            sources,
+            targets,
+            kind,
+        };
+    }
+    let (from, to) = match kind {
+        EdgeKind::Reverse => (to, from),
+        _ => (from, to),
+    };
+    AST::Edge {
+        from: Box::new(from.clone()),
+        to: Box::new(to.clone()),
+        kind,
+    }
 }
 
 pub struct Parser {
     cur: usize,
-    toks: Vec<Token>,
+    toks: Vec<Spanned<Token>>,
+    filename: Option<String>,
+    recovering: bool,
+    errors: Vec<Error>,
+    duplicate_blocks: DuplicateBlocks,
+    grouped_edges: bool,
+    end: Option<Span>,
 }
 
 impl Parser {
-    pub fn new(toks: Vec<Token>) -> Self {
-        Self { cur: 0, toks: toks }
+    pub fn new(toks: Vec<Spanned<Token>>) -> Self {
+        Self {
+            cur: 0,
+            toks,
+            filename: None,
+            recovering: false,
+            errors: Vec::new(),
+            duplicate_blocks: DuplicateBlocks::default(),
+            grouped_edges: false,
+            end: None,
+        }
+    }
+    pub fn with_filename(mut self, filename: &str) -> Self {
+        self.filename = Some(filename.to_string());
+        self
+    }
+    pub fn duplicate_blocks(mut self, policy: DuplicateBlocks) -> Self {
+        self.duplicate_blocks = policy;
+        self
+    }
+    /// Keeps edges with groups such as `(X, Y) -> Z` as a single edge whose
+    /// end is a `GValue::Vec`, instead of expanding them into `X -> Z` and
+    /// `Y -> Z`.
+    pub fn grouped_edges(mut self, keep: bool) -> Self {
+        self.grouped_edges = keep;
+        self
+    }
+    /// Sets where errors at the end of input are reported, usually
+    /// `Scanner::end_span`. Without it the end is found from the byte
+    /// length of the last token, which is only exact for ASCII text.
+    pub fn with_end_span(mut self, span: Span) -> Self {
+        self.end = Some(span);
+        self
+    }
+    fn peek(&self) -> Option<&Token> {
+        self.toks.get(self.cur).map(|tok| &tok.node)
+    }
+    fn next_token(&mut self) -> Option<Token> {
+        let tok = self.peek().cloned();
+        if tok.is_some() {
+            self.cur += 1;
+        }
+        tok
+    }
+    fn location(&self, span: Span) -> Location {
+        Location {
+            file: self.filename.clone(),
+            span,
+        }
+    }
+    fn error(&self, expected: &'static str) -> Error {
+        match self.toks.get(self.cur) {
+            Some(tok) => Error::UnexpectedToken {
+                expected,
+                found: tok.node.describe(),
+                location: self.location(tok.span),
+            },
+            None => Error::UnexpectedEof {
+                expected,
+                location: self.location(self.eof_span()),
+            },
+        }
+    }
+    fn eof_span(&self) -> Span {
+        match self.toks.last() {
+            Some(tok) if tok.node == Token::Newline => Span::new(
+                tok.span.start,
+                tok.span.start,
+                tok.span.line,
+                tok.span.column,
+            ),
+            Some(tok) => self.end.unwrap_or(Span::new(
+                tok.span.end,
+                tok.span.end,
+                tok.span.line,
+                tok.span.column + tok.span.end - tok.span.start,
+            )),
+            None => Span::new(0, 0, 1, 1),
+        }
+    }
+    fn start_span(&self) -> Span {
+        self.toks
+            .get(self.cur)
+            .map_or_else(|| self.eof_span(), |tok| tok.span)
+    }
+    fn span_from(&self, start: Span) -> Span {
+        match self.cur.checked_sub(1).and_then(|prev| self.toks.get(prev)) {
+            Some(prev) => start.to(prev.span),
+            None => start,
+        }
+    }
+    fn expect(&mut self, expected_tok: Token, expected: &'static str) -> Result<(), Error> {
+        if self.peek() == Some(&expected_tok) {
+            self.cur += 1;
+            Ok(())
+        } else {
+            Err(self.error(expected))
+        }
     }
     fn skip_blank(&mut self) {
-        for i in self.cur..self.toks.len() {
-            match self.toks[i] {
-                Token::Newline => {}
-                Token::Whitespace => {}
-                _ => {
-                    self.cur = i;
-                    break;
-                }
-            }
+        while let Some(Token::Newline) | Some(Token::Whitespace) = self.peek() {
+            self.cur += 1;
         }
     }
     fn skip_whitespace(&mut self) {
-        for i in self.cur..self.toks.len() {
-            match self.toks[i] {
-                Token::Whitespace => {}
-                _ => {
-                    self.cur = i;
-                    break;
-                }
-            }
+        while let Some(Token::Whitespace) = self.peek() {
+            self.cur += 1;
         }
     }
-    fn parse_block(&mut self) -> Result<AST, String> {
-        self.skip_blank();
-        let first = self.toks[self.cur].clone();
-        match first {
-            Token::Symbol(Symbol::LeftBracket) => {
+    fn expect_line_end(&mut self) -> Result<(), Error> {
+        self.skip_whitespace();
+        match self.peek() {
+            None => Ok(()),
+            Some(Token::Newline) => {
                 self.cur += 1;
+                Ok(())
             }
-            _ => {
-                panic!("parsing error: expect [");
+            Some(_) => Err(self.error("newline")),
+        }
+    }
+    fn expect_stmt_end(&mut self, stmt: &Spanned<AST>) -> Result<(), Error> {
+        self.skip_whitespace();
+        match (&stmt.node, self.toks.get(self.cur)) {
+            (AST::Symbol(name), Some(tok)) if tok.node.starts_value() => {
+                Err(Error::MissingParentheses {
+                    message: name.clone(),
+                    message_span: stmt.span,
+                    location: self.location(tok.span),
+                })
             }
+            _ => self.expect_line_end(),
         }
-        let second = self.toks[self.cur].clone();
-        self.cur += 1;
-        let name = match second {
-            Token::Identifier(name) => Some(name),
-            _ => None,
+    }
+    fn parse_block(&mut self) -> Result<Spanned<AST>, Error> {
+        self.skip_blank();
+        let start = self.start_span();
+        self.expect(Token::Symbol(Symbol::LeftBracket), "`[`")?;
+        let name = match self.peek() {
+            Some(Token::Identifier(name)) => name.clone(),
+            _ => return Err(self.error("block name")),
         };
-        let name = name.expect("parsing error: expect identifier");
-        let third = self.toks[self.cur].clone();
+        let span = self.start_span();
+        let first = self.cur;
         self.cur += 1;
-        match third {
-            Token::Symbol(Symbol::RightBracket) => {
-                self.skip_whitespace();
-                let fourth = self.toks[self.cur].clone();
-                self.cur += 1;
-                match fourth {
-                    Token::Newline => {
-                        let content = self.parse_content()?;
-                        let block = AST::Block {
-                            name: name,
-                            content: content,
-                        };
-                        Ok(block)
-                    }
-                    _ => panic!("parsing error: expect newline"),
-                }
-            }
-            _ => {
-                panic!("parsing error: expect ]");
-            }
+        // a nested block `[Model.Attack]` keeps its dotted path as the name,
+        // so a quoted name can not contain a `.` of its own
+        let path = self.parse_path(name, span)?;
+        let quoted_dot = self.toks[first..self.cur]
+            .iter()
+            .find(|tok| matches!(&tok.node, Token::Identifier(name) if name.contains('.')));
+        if let Some(tok) = quoted_dot {
+            return Err(Error::UnexpectedToken {
+                expected: "a block name without `.`",
+                found: tok.node.describe(),
+                location: self.location(tok.span),
+            });
         }
+        let name = match path {
+            Spanned {
+                node: AST::Path(names),
+                span,
+            } => Spanned::new(names.join("."), span),
+            Spanned {
+                node: AST::Symbol(name),
+                span,
+            } => Spanned::new(name, span),
+            _ => unreachable!(),
+        };
+        self.expect(Token::Symbol(Symbol::RightBracket), "`]`")?;
+        let header = self.span_from(start);
+        self.expect_line_end()?;
+        let content = self.parse_content()?;
+        let span = content.last().map_or(header, |last| header.to(last.span));
+        Ok(Spanned::new(AST::Block { name, content }, span))
     }
-    fn parse_content(&mut self) -> Result<Vec<AST>, String> {
-        let mut content: Vec<AST> = Vec::new();
-        while self.cur < self.toks.len() {
-            let head = self.toks[self.cur].clone();
+    fn parse_content(&mut self) -> Result<Vec<Spanned<AST>>, Error> {
+        let mut content: Vec<Spanned<AST>> = Vec::new();
+        let mut ids: Vec<(String, Span)> = Vec::new();
+        while let Some(head) = self.peek() {
             match head {
                 Token::Symbol(Symbol::LeftBracket) => {
                     break;
                 }
-                Token::Newline => {
+                Token::Newline | Token::Whitespace => {
                     self.cur += 1;
-                    break;
                 }
-                Token::Identifier(_) | Token::Number(_) | Token::String(_) => {
-                    let first = self.parse_target()?;
-                    self.skip_whitespace();
-                    let second = self.toks[self.cur].clone();
-                    self.cur += 1;
-                    match second {
-                        Token::Newline => {
-                            content.push(first);
-                        }
-                        Token::Symbol(Symbol::Colon) => match &first {
-                            AST::Edge { from: _, to: _ } => {
-                                self.skip_blank();
-                                let third = self.parse_value()?;
-                                self.skip_whitespace();
-                                let fourth = self.toks[self.cur].clone();
-                                self.cur += 1;
-                                match fourth {
-                                    Token::Newline => {
-                                        let target = first.clone();
-                                        let stmt = third.clone();
-                                        content.push(AST::EdgeDef {
-                                            target: Box::new(target),
-                                            stmt: Box::new(stmt),
-                                        });
-                                    }
-                                    _ => panic!("parsing error: expect newline"),
-                                }
-                            }
-                            _ => panic!("parsing error: edge : stmt ?"),
-                        },
-                        Token::Symbol(Symbol::Equal) => match &first {
-                            AST::Symbol(_) | AST::String(_) | AST::Number(_) => {
-                                self.skip_blank();
-                                let third = self.parse_value()?;
-                                self.skip_whitespace();
-                                let fourth = self.toks[self.cur].clone();
-                                self.cur += 1;
-                                match fourth {
-                                    Token::Newline => {
-                                        let target = first.clone();
-                                        let stmt = third.clone();
-                                        content.push(AST::LeafDef {
-                                            target: Box::new(target),
-                                            stmt: Box::new(stmt),
-                                        });
-                                    }
-                                    _ => panic!("parsing error: expect newline"),
+                Token::Identifier(_)
+                | Token::Integer(_)
+                | Token::Float(_)
+                | Token::String(_)
+                | Token::Bool(_)
+                | Token::Null
+                | Token::Symbol(Symbol::LeftParen) => {
+                    let entry = self.parse_entry();
+                    match self.recover(entry)? {
+                        Spanned {
+                            node: AST::Chain(hops),
+                            ..
+                        } => content.extend(hops),
+                        entry => {
+                            if let Err(err) = self.check_edge_id(&entry, &mut ids) {
+                                if !self.recovering {
+                                    return Err(err);
                                 }
+                                self.errors.push(err);
                             }
-                            _ => panic!("parsing error: leaf = stmt ?"),
-                        },
-                        _ => panic!("parsing error: expect : or newline"),
+                            content.push(entry);
+                        }
                     }
                 }
-                _ => panic!("parsing error: expect newline or identifier"),
+                _ => {
+                    let err = Err(self.error("newline or identifier"));
+                    content.push(self.recover(err)?);
+                }
             }
         }
         Ok(content)
     }
 
-    fn parse_key(&mut self) -> Result<AST, String> {
-        let first = self.toks[self.cur].clone();
-        self.cur += 1;
-        match first {
-            Token::Identifier(name) => Ok(AST::Symbol(name.to_string())),
-            Token::String(string) => Ok(AST::String(string.to_string())),
-            Token::Number(number) => Ok(AST::Number(number)),
-            _ => panic!("parsing error: expect identifier or string or number"),
+    fn parse_entry(&mut self) -> Result<Spanned<AST>, Error> {
+        let first = self.parse_target()?;
+        self.skip_whitespace();
+        match self.peek() {
+            None => Ok(first),
+            Some(Token::Newline) => {
+                self.cur += 1;
+                Ok(first)
+            }
+            Some(Token::Symbol(Symbol::Colon)) => match &first.node {
+                AST::Edge { .. } | AST::HyperEdge { .. } | AST::Chain(_) => {
+                    self.parse_edge_def(first)
+                }
+                AST::Symbol(id) => {
+                    let id = Spanned::new(id.clone(), first.span);
+                    self.parse_named_edge(id)
+                }
+                _ => Err(self.error("`=`, `->` or newline")),
+            },
+            Some(Token::Symbol(Symbol::Equal)) => match &first.node {
+                AST::Symbol(_)
+                | AST::Path(_)
+                | AST::String(_)
+                | AST::Integer(_)
+                | AST::Float(_) => {
+                    self.cur += 1;
+                    self.skip_blank();
+                    let stmt = self.parse_value()?;
+                    let span = first.span.to(stmt.span);
+                    self.expect_stmt_end(&stmt)?;
+                    Ok(Spanned::new(
+                        AST::LeafDef {
+                            target: Box::new(first),
+                            stmt: Box::new(stmt),
+                        },
+                        span,
+                    ))
+                }
+                _ => Err(self.error("`:` or newline")),
+            },
+            Some(_) => Err(self.error("`:`, `=` or newline")),
         }
     }
 
-    fn parse_target(&mut self) -> Result<AST, String> {
-        let left = self.parse_key()?;
-        let cur = self.cur;
-        self.skip_blank();
-        let second = self.toks[self.cur].clone();
+    /// Checks that the id of a named edge is not used by an earlier edge of
+    /// the block.
+    fn check_edge_id(
+        &self,
+        entry: &Spanned<AST>,
+        ids: &mut Vec<(String, Span)>,
+    ) -> Result<(), Error> {
+        let (name, span) = match &entry.node {
+            AST::Named { id, .. } => (id.node.clone(), id.span),
+            _ => return Ok(()),
+        };
+        if let Some((_, first)) = ids.iter().find(|(first, _)| *first == name) {
+            return Err(Error::DuplicateEdgeId {
+                id: name,
+                first: *first,
+                location: self.location(span),
+            });
+        }
+        ids.push((name, span));
+        Ok(())
+    }
+
+    /// Parses the statement `: stmt` after an edge, applying it to every
+    /// hop of a chain.
+    fn parse_edge_def(&mut self, target: Spanned<AST>) -> Result<Spanned<AST>, Error> {
         self.cur += 1;
-        match second {
-            Token::Arrow => {
-                self.skip_blank();
-                let right = self.parse_key()?;
-                Ok(AST::Edge {
-                    from: Box::new(left),
-                    to: Box::new(right),
-                })
-            }
+        self.skip_blank();
+        let stmt = self.parse_value()?;
+        self.expect_stmt_end(&stmt)?;
+        let span = target.span.to(stmt.span);
+        let hops = match target.node {
+            AST::Chain(hops) => hops,
             _ => {
-                self.cur = cur;
-                Ok(left)
+                return Ok(Spanned::new(
+                    AST::EdgeDef {
+                        target: Box::new(target),
+                        stmt: Box::new(stmt),
+                    },
+                    span,
+                ))
             }
+        };
+        // the statement applies to every hop, which keeps the span of its
+        // own `from -> to`
+        let hops = hops
+            .into_iter()
+            .map(|hop| {
+                let span = hop.span;
+                Spanned::new(
+                    AST::EdgeDef {
+                        target: Box::new(hop),
+                        stmt: Box::new(stmt.clone()),
+                    },
+                    span,
+                )
+            })
+            .collect();
+        Ok(Spanned::new(AST::Chain(hops), span))
+    }
+
+    /// Parses the edge after its id `id:`, with its statement if any.
+    fn parse_named_edge(&mut self, id: Spanned<String>) -> Result<Spanned<AST>, Error> {
+        self.cur += 1;
+        self.skip_whitespace();
+        let edge = self.parse_target()?;
+        if !matches!(edge.node, AST::Edge { .. } | AST::HyperEdge { .. }) {
+            return Err(Error::UnexpectedToken {
+                expected: "a single edge after an edge id",
+                found: match edge.node {
+                    AST::Chain(_) => "several edges".to_string(),
+                    _ => "a key".to_string(),
+                },
+                location: self.location(edge.span),
+            });
         }
+        self.skip_whitespace();
+        let edge = match self.peek() {
+            Some(Token::Symbol(Symbol::Colon)) => self.parse_edge_def(edge)?,
+            _ => {
+                self.expect_line_end()?;
+                edge
+            }
+        };
+        let span = id.span.to(edge.span);
+        Ok(Spanned::new(
+            AST::Named {
+                id,
+                edge: Box::new(edge),
+            },
+            span,
+        ))
     }
 
-    fn parse_value(&mut self) -> Result<AST, String> {
-        let first = self.toks[self.cur].clone();
-        match first {
-            Token::Symbol(Symbol::LeftBrace) => self.parse_struct(),
-            Token::Symbol(Symbol::LeftParen) => self.parse_list(),
-            Token::String(string) => {
-                self.cur += 1;
-                Ok(AST::String(string))
+    /// In recovering mode, records the error and skips to the start of the
+    /// next line, returning an error node covering the skipped tokens.
+    fn recover(&mut self, result: Result<Spanned<AST>, Error>) -> Result<Spanned<AST>, Error> {
+        let err = match result {
+            Ok(node) => return Ok(node),
+            Err(err) if !self.recovering => return Err(err),
+            Err(err) => err,
+        };
+        let start = err.span().unwrap_or_else(|| self.start_span());
+        let mut lexical = false;
+        while let Some(tok) = self.next_token() {
+            match tok {
+                Token::Newline => break,
+                Token::Error => lexical = true,
+                _ => {}
             }
-            Token::Number(number) => {
-                self.cur += 1;
-                Ok(AST::Number(number))
+        }
+        // errors caused by a malformed token were already reported by the
+        // scanner
+        if !lexical && !self.at_error_token(&err) {
+            self.errors.push(err);
+        }
+        Ok(Spanned::new(AST::Error, self.span_from(start)))
+    }
+
+    fn at_error_token(&self, err: &Error) -> bool {
+        self.toks
+            .iter()
+            .any(|tok| tok.node == Token::Error && Some(tok.span) == err.span())
+    }
+
+    fn skip_to_block(&mut self) {
+        while self.cur < self.toks.len() {
+            let line_start = self.cur == 0 || self.toks[self.cur - 1].node == Token::Newline;
+            if line_start && self.peek() == Some(&Token::Symbol(Symbol::LeftBracket)) {
+                break;
             }
-            Token::Identifier(_) => self.parse_message(),
-            _ => panic!("parsing error: expect {, string, number, or identifier"),
+            self.cur += 1;
         }
     }
 
-    fn parse_pair(&mut self) -> Result<AST, String> {
-        let pair_left = self.parse_key()?;
-        self.skip_blank();
-        let second = self.toks[self.cur].clone();
+    fn parse_key(&mut self) -> Result<Spanned<AST>, Error> {
+        let key = match self.peek() {
+            Some(Token::Identifier(name)) => AST::Symbol(name.to_string()),
+            Some(Token::String(string)) => AST::String(string.to_string()),
+            Some(Token::Integer(number)) => AST::Integer(*number),
+            Some(Token::Float(number)) => AST::Float(*number),
+            Some(Token::Bool(_)) | Some(Token::Null) => {
+                let tok = &self.toks[self.cur];
+                return Err(Error::ReservedKeyword {
+                    keyword: tok.node.describe().trim_matches('`').to_string(),
+                    location: self.location(tok.span),
+                });
+            }
+            _ => return Err(self.error("identifier, string or number")),
+        };
+        let span = self.start_span();
         self.cur += 1;
-        match second {
-            Token::Symbol(Symbol::Colon) => {}
-            _ => panic!("parsing error: expect :"),
+        match key {
+            AST::Symbol(name) => self.parse_path(name, span),
+            key => Ok(Spanned::new(key, span)),
         }
-        self.skip_blank();
-        Ok(AST::LeafDef {
-            target: Box::new(pair_left),
-            stmt: Box::new(self.parse_value()?),
-        })
     }
 
-    fn parse_list(&mut self) -> Result<AST, String> {
-        let first = self.toks[self.cur].clone();
-        self.cur += 1;
-        match first {
-            Token::Symbol(Symbol::LeftParen) => {}
-            _ => panic!("parsing error: expect ("),
+    /// Parses the rest of a dotted path `Z.has_class` after its first name,
+    /// which is a plain symbol if no `.` follows. The names and dots can not
+    /// be separated by whitespace.
+    fn parse_path(&mut self, first: String, start: Span) -> Result<Spanned<AST>, Error> {
+        let mut names = vec![first];
+        while self.peek() == Some(&Token::Symbol(Symbol::Point)) {
+            self.cur += 1;
+            match self.peek() {
+                Some(Token::Identifier(name)) => names.push(name.clone()),
+                _ => return Err(self.error("identifier after `.`")),
+            }
+            self.cur += 1;
         }
-        let mut content: Vec<AST> = Vec::new();
-        self.skip_blank();
-        while self.cur < self.toks.len() {
-            let second = self.toks[self.cur].clone();
-            match second {
-                Token::Symbol(Symbol::RightParen) => {
-                    return Ok(AST::List(content));
+        let node = match names.len() {
+            1 => AST::Symbol(names.pop().unwrap()),
+            _ => AST::Path(names),
+        };
+        Ok(Spanned::new(node, self.span_from(start)))
+    }
+
+    /// Parses a key, a group of keys `(A, B)` or the nodes `X + Y` of a
+    /// hyperedge at an end of an edge.
+    fn parse_endpoint(&mut self) -> Result<Spanned<AST>, Error> {
+        if self.peek() != Some(&Token::Symbol(Symbol::LeftParen)) {
+            let mut keys = vec![self.parse_key()?];
+            loop {
+                let cur = self.cur;
+                self.skip_whitespace();
+                if self.peek() != Some(&Token::Symbol(Symbol::Plus)) {
+                    self.cur = cur;
+                    break;
                 }
-                _ => {}
+                self.cur += 1;
+                self.skip_blank();
+                keys.push(self.parse_key()?);
             }
-            let value = self.parse_value()?;
-            content.push(value);
+            if keys.len() == 1 {
+                return Ok(keys.pop().unwrap());
+            }
+            let span = keys[0].span.to(keys[keys.len() - 1].span);
+            return Ok(Spanned::new(AST::Join(keys), span));
+        }
+        let start = self.start_span();
+        self.cur += 1;
+        let mut keys = Vec::new();
+        self.skip_blank();
+        loop {
+            keys.push(self.parse_key()?);
             self.skip_blank();
-            let comma = self.toks[self.cur].clone();
-            match comma {
-                Token::Symbol(Symbol::Comma) => {
-                    self.cur += 1;
+            if self.peek() != Some(&Token::Symbol(Symbol::Comma)) {
+                break;
+            }
+            self.cur += 1;
+            self.skip_blank();
+            if self.peek() == Some(&Token::Symbol(Symbol::RightParen)) {
+                break;
+            }
+        }
+        self.expect(Token::Symbol(Symbol::RightParen), "`,` or `)`")?;
+        Ok(Spanned::new(AST::List(keys), self.span_from(start)))
+    }
+
+    /// Parses a key, an edge `A -> B` or a chain `A -> B -> C`. Each hop of
+    /// a chain keeps its own operator, e.g. `A -> B -- C`. Either end of a
+    /// hop may be a group `(A, B)`, which is expanded into an edge for each
+    /// pair of keys unless `grouped_edges` is set.
+    fn parse_target(&mut self) -> Result<Spanned<AST>, Error> {
+        let mut keys = vec![self.parse_endpoint()?];
+        let mut kinds = Vec::new();
+        loop {
+            let cur = self.cur;
+            self.skip_blank();
+            match self.next_token() {
+                Some(Token::Arrow(kind)) => {
+                    kinds.push(kind);
                     self.skip_blank();
+                    keys.push(self.parse_endpoint()?);
                 }
                 _ => {
+                    self.cur = cur;
                     break;
                 }
             }
         }
-        let third = self.toks[self.cur].clone();
-        self.cur += 1;
-        match third {
-            Token::Symbol(Symbol::RightParen) => Ok(AST::List(content)),
-            _ => panic!("parsing error: expect )"),
+        if kinds.is_empty() {
+            return match keys.pop().unwrap() {
+                Spanned {
+                    node: AST::List(_) | AST::Join(_),
+                    ..
+                } => {
+                    self.skip_whitespace();
+                    Err(self.error("an edge operator"))
+                }
+                key => Ok(key),
+            };
         }
+        let span = keys[0].span.to(keys[keys.len() - 1].span);
+        let mut hops: Vec<Spanned<AST>> = Vec::new();
+        for (hop, kind) in keys.windows(2).zip(kinds) {
+            let span = hop[0].span.to(hop[1].span);
+            let (froms, tos) = if self.grouped_edges {
+                (slice::from_ref(&hop[0]), slice::from_ref(&hop[1]))
+            } else {
+                (group_keys(&hop[0]), group_keys(&hop[1]))
+            };
+            for from in froms {
+                for to in tos {
+                    hops.push(Spanned::new(edge(from, to, kind), span));
+                }
+            }
+        }
+        Ok(match hops.len() {
+            1 => hops.pop().unwrap(),
+            _ => Spanned::new(AST::Chain(hops), span),
+        })
     }
 
-    fn parse_struct(&mut self) -> Result<AST, String> {
-        let first = self.toks[self.cur].clone();
-        self.cur += 1;
-        match first {
-            Token::Symbol(Symbol::LeftBrace) => {}
-            _ => panic!("parsing error: expect {"),
+    fn parse_value(&mut self) -> Result<Spanned<AST>, Error> {
+        match self.peek() {
+            Some(Token::Symbol(Symbol::LeftBrace)) => self.parse_struct(),
+            Some(Token::Symbol(Symbol::LeftParen)) => self.parse_list(),
+            Some(Token::String(_)) | Some(Token::Integer(_)) | Some(Token::Float(_)) => {
+                self.parse_key()
+            }
+            Some(Token::Bool(value)) => {
+                let value = *value;
+                let span = self.start_span();
+                self.cur += 1;
+                Ok(Spanned::new(AST::Bool(value), span))
+            }
+            Some(Token::Null) => {
+                let span = self.start_span();
+                self.cur += 1;
+                Ok(Spanned::new(AST::Null, span))
+            }
+            Some(Token::Identifier(_)) => self.parse_message(),
+            _ => Err(self.error("`{`, `(`, string, number or identifier")),
         }
-        let mut content: Vec<AST> = Vec::new();
+    }
+
+    fn parse_pair(&mut self) -> Result<Spanned<AST>, Error> {
+        let pair_left = self.parse_key()?;
+        self.skip_blank();
+        self.expect(Token::Symbol(Symbol::Colon), "`:`")?;
+        self.skip_blank();
+        let pair_right = self.parse_value()?;
+        let span = pair_left.span.to(pair_right.span);
+        Ok(Spanned::new(
+            AST::LeafDef {
+                target: Box::new(pair_left),
+                stmt: Box::new(pair_right),
+            },
+            span,
+        ))
+    }
+
+    fn parse_list(&mut self) -> Result<Spanned<AST>, Error> {
+        let start = self.start_span();
+        let content = self.parse_args()?;
+        Ok(Spanned::new(AST::List(content), self.span_from(start)))
+    }
+
+    fn parse_struct(&mut self) -> Result<Spanned<AST>, Error> {
+        let start = self.start_span();
+        self.expect(Token::Symbol(Symbol::LeftBrace), "`{`")?;
+        let mut content: Vec<Spanned<AST>> = Vec::new();
+        let mut keys: Vec<Spanned<GValue>> = Vec::new();
         self.skip_blank();
         while self.cur < self.toks.len() {
-            let second = self.toks[self.cur].clone();
-            match second {
-                Token::Symbol(Symbol::RightBrace) => {
-                    return Ok(AST::List(content));
-                }
-                _ => {}
+            if let Some(Token::Symbol(Symbol::RightBrace)) = self.peek() {
+                break;
             }
             let pair = self.parse_pair()?;
+            if let AST::LeafDef { target, .. } = &pair.node {
+                let key = Spanned::<GValue>::from((**target).clone());
+                if let Some(first) = keys.iter().find(|first| first.node.matches_key(&key.node)) {
+                    return Err(Error::DuplicateKey {
+                        key: key.node.to_string(),
+                        first: first.span,
+                        location: self.location(key.span),
+                    });
+                }
+                keys.push(key);
+            }
             content.push(pair);
             self.skip_blank();
-            let comma = self.toks[self.cur].clone();
-            match comma {
-                Token::Symbol(Symbol::Comma) => {
+            match self.peek() {
+                Some(Token::Symbol(Symbol::Comma)) => {
                     self.cur += 1;
                     self.skip_blank();
                 }
@@ -537,57 +1323,45 @@ impl Parser {
                 }
             }
         }
-        let third = self.toks[self.cur].clone();
-        self.cur += 1;
-        match third {
-            Token::Symbol(Symbol::RightBrace) => Ok(AST::Struct(content)),
-            _ => panic!("parsing error: expect }"),
-        }
+        self.expect(Token::Symbol(Symbol::RightBrace), "`,` or `}`")?;
+        Ok(Spanned::new(AST::Struct(content), self.span_from(start)))
     }
 
-    fn parse_message(&mut self) -> Result<AST, String> {
-        let first = self.toks[self.cur].clone();
+    fn parse_message(&mut self) -> Result<Spanned<AST>, Error> {
+        let start = self.start_span();
+        let message_name = match self.peek() {
+            Some(Token::Identifier(name)) => name.clone(),
+            _ => return Err(self.error("identifier")),
+        };
         self.cur += 1;
-        let message_name: String;
-        match first {
-            Token::Identifier(name) => {
-                message_name = name;
+        match self.peek() {
+            Some(Token::Symbol(Symbol::LeftParen)) => {
+                let args = self.parse_args()?;
+                Ok(Spanned::new(
+                    AST::Message {
+                        name: message_name,
+                        args,
+                    },
+                    self.span_from(start),
+                ))
             }
-            _ => panic!("parsing error: expect identifier"),
-        }
-        let second = self.toks[self.cur].clone();
-        match second {
-            Token::Symbol(Symbol::LeftParen) => Ok(AST::Message {
-                name: message_name,
-                args: self.parse_args()?,
-            }),
-            _ => Ok(AST::Symbol(message_name)),
+            _ => self.parse_path(message_name, start),
         }
     }
 
-    fn parse_args(&mut self) -> Result<Vec<AST>, String> {
-        let first = self.toks[self.cur].clone();
-        self.cur += 1;
-        match first {
-            Token::Symbol(Symbol::LeftParen) => {}
-            _ => panic!("parsing error: expect ("),
-        }
-        let mut content: Vec<AST> = Vec::new();
+    fn parse_args(&mut self) -> Result<Vec<Spanned<AST>>, Error> {
+        self.expect(Token::Symbol(Symbol::LeftParen), "`(`")?;
+        let mut content: Vec<Spanned<AST>> = Vec::new();
         self.skip_blank();
         while self.cur < self.toks.len() {
-            let second = self.toks[self.cur].clone();
-            match second {
-                Token::Symbol(Symbol::RightParen) => {
-                    break;
-                }
-                _ => {}
+            if let Some(Token::Symbol(Symbol::RightParen)) = self.peek() {
+                break;
             }
             let value = self.parse_value()?;
             content.push(value);
             self.skip_blank();
-            let comma = self.toks[self.cur].clone();
-            match comma {
-                Token::Symbol(Symbol::Comma) => {
+            match self.peek() {
+                Some(Token::Symbol(Symbol::Comma)) => {
                     self.cur += 1;
                     self.skip_blank();
                 }
@@ -596,66 +1370,113 @@ impl Parser {
                 }
             }
         }
-        let third = self.toks[self.cur].clone();
-        self.cur += 1;
-        match third {
-            Token::Symbol(Symbol::RightParen) => Ok(content),
-            _ => panic!("parsing error: expect )"),
-        }
+        self.expect(Token::Symbol(Symbol::RightParen), "`,` or `)`")?;
+        Ok(content)
     }
 
-    fn get_ast(&mut self) -> Result<Vec<AST>, String> {
-        let mut blocks: Vec<AST> = Vec::new();
+    pub fn parse_ast(&mut self) -> Result<Vec<Spanned<AST>>, Error> {
+        let mut blocks: Vec<Spanned<AST>> = Vec::new();
+        self.skip_blank();
         while self.cur < self.toks.len() {
-            let block = self.parse_block()?;
-            blocks.push(block);
+            match self.parse_block() {
+                Ok(block) => blocks.push(block),
+                Err(err) if self.recovering => {
+                    let start = err.span().unwrap_or_else(|| self.start_span());
+                    if !self.at_error_token(&err) {
+                        self.errors.push(err);
+                    }
+                    self.skip_to_block();
+                    blocks.push(Spanned::new(AST::Error, self.span_from(start)));
+                }
+                Err(err) => return Err(err),
+            }
+            self.skip_blank();
         }
         Ok(blocks)
     }
 
-    pub fn parse(&mut self) -> Result<HashMap<String, GValue>, String> {
-        let root_ast = self.get_ast()?;
-        let mut root: HashMap<String, GValue> = HashMap::new();
-        let blocks: Vec<GValue> = root_ast.into_iter().map(GValue::from).collect();
-        for block in blocks {
-            match block {
-                GValue::Pair(key, value) => match *key {
-                    GValue::String(key_string) => {
-                        root.entry(key_string).or_insert(*value);
+    /// Parses as much as possible, returning the partial AST with
+    /// `AST::Error` nodes in place of broken entries and every error found.
+    pub fn parse_ast_recovering(&mut self) -> (Vec<Spanned<AST>>, Vec<Error>) {
+        self.recovering = true;
+        let blocks = self.parse_ast().unwrap_or_default();
+        self.recovering = false;
+        (blocks, std::mem::take(&mut self.errors))
+    }
+
+    pub fn parse(&mut self) -> Result<Document, Error> {
+        let root_ast = self.parse_ast()?;
+        self.build_document(root_ast)
+    }
+
+    pub fn parse_recovering(&mut self) -> (Document, Vec<Error>) {
+        self.recovering = true;
+        let document = self
+            .parse_ast()
+            .and_then(|root_ast| self.build_document(root_ast))
+            .unwrap_or_default();
+        self.recovering = false;
+        (document, std::mem::take(&mut self.errors))
+    }
+
+    fn build_document(&mut self, root_ast: Vec<Spanned<AST>>) -> Result<Document, Error> {
+        let mut document = Document::new();
+        for block in root_ast {
+            let (name, content) = match block.node {
+                AST::Block { name, content } => (name, content),
+                _ => continue,
+            };
+            let entries = content
+                .into_iter()
+                .filter(|entry| !matches!(entry.node, AST::Error))
+                .map(Spanned::<GValue>::from);
+            match (document.get_mut(&name.node), self.duplicate_blocks) {
+                (Some(first), DuplicateBlocks::Error) => {
+                    let err = Error::DuplicateBlock {
+                        name: name.node,
+                        first: first.name_span,
+                        location: self.location(name.span),
+                    };
+                    if !self.recovering {
+                        return Err(err);
                     }
-                    _ => panic!("convert error: key should be String"),
-                },
-                _ => panic!("convert error: invalid block syntax"),
+                    self.errors.push(err);
+                }
+                (Some(first), DuplicateBlocks::Merge) => {
+                    first.entries.extend(entries);
+                    first.span = first.span.to(block.span);
+                }
+                _ => document.push(Block {
+                    name: name.node,
+                    name_span: name.span,
+                    span: block.span,
+                    entries: entries.collect(),
+                }),
             }
         }
-        Ok(root)
+        Ok(document)
     }
 }
 
-#[derive(Debug)]
-pub enum GValue {
-    String(String),
-    Number(f64),
-    Symbol(String),
-    Message(String, Vec<GValue>),
-    Edge(Box<GValue>, Box<GValue>),
-    Vec(Vec<GValue>),
-    Pair(Box<GValue>, Box<GValue>),
-}
-
 impl From<AST> for GValue {
     fn from(ast: AST) -> Self {
         match ast {
-            AST::String(string) => GValue::String(string.to_string()),
-            AST::Number(number) => GValue::Number(number),
-            AST::Symbol(name) => GValue::Symbol(name.to_string()),
+            AST::String(string) => GValue::String(string),
+            AST::Integer(number) => GValue::Integer(number),
+            AST::Float(number) => GValue::Float(number),
+            AST::Bool(value) => GValue::Bool(value),
+            AST::Null => GValue::Null,
+            AST::Symbol(name) => GValue::Symbol(name),
+            AST::Path(names) => GValue::Path(names),
             AST::LeafDef { target, stmt } => GValue::Pair(
                 Box::new(GValue::from(*target)),
                 Box::new(GValue::from(*stmt)),
             ),
-            AST::Edge { from, to } => {
-                GValue::Edge(Box::new(GValue::from(*from)), Box::new(GValue::from(*to)))
-            }
+            AST::Edge { from, to, kind } => GValue::Edge(
+                Box::new(GValue::from(*from)),
+                Box::new(GValue::from(*to)),
+                kind,
+            ),
             AST::EdgeDef { target, stmt } => GValue::Pair(
                 Box::new(GValue::from(*target)),
                 Box::new(GValue::from(*stmt)),
@@ -663,12 +1484,51 @@ impl From<AST> for GValue {
             AST::Message { name, args } => {
                 GValue::Message(name, args.into_iter().map(GValue::from).collect())
             }
-            AST::Struct(content) => GValue::Vec(content.into_iter().map(GValue::from).collect()),
-            AST::List(content) => GValue::Vec(content.into_iter().map(GValue::from).collect()),
+            AST::Struct(content) => GValue::Map(
+                content
+                    .into_iter()
+                    .filter_map(|pair| match pair.node {
+                        AST::LeafDef { target, stmt } => {
+                            Some((GValue::from(*target), GValue::from(*stmt)))
+                        }
+                        _ => None,
+                    })
+                    .collect(),
+            ),
+            AST::HyperEdge {
+                sources,
+                targets,
+                kind,
+            } => GValue::HyperEdge {
+                sources: sources.into_iter().map(GValue::from).collect(),
+                targets: targets.into_iter().map(GValue::from).collect(),
+                kind,
+            },
+            AST::Named { id, edge } => GValue::Named {
+                id: id.node,
+                edge: Box::new(GValue::from(*edge)),
+            },
+            AST::List(content) | AST::Chain(content) | AST::Join(content) => {
+                GValue::Vec(content.into_iter().map(GValue::from).collect())
+            }
             AST::Block { name, content } => GValue::Pair(
-                Box::new(GValue::String(name.to_string())),
+                Box::new(GValue::String(name.node)),
                 Box::new(GValue::Vec(content.into_iter().map(GValue::from).collect())),
             ),
+            // error nodes only exist in recovered trees and carry no value
+            AST::Error => GValue::Vec(Vec::new()),
         }
     }
 }
+
+impl From<Spanned<AST>> for GValue {
+    fn from(ast: Spanned<AST>) -> Self {
+        GValue::from(ast.node)
+    }
+}
+
+impl From<Spanned<AST>> for Spanned<GValue> {
+    fn from(ast: Spanned<AST>) -> Self {
+        Spanned::new(GValue::from(ast.node), ast.span)
+    }
+}
@@ -0,0 +1,401 @@
+use crate::document::{Block, Document};
+use crate::error::Error;
+use crate::value::{leaf_def, EdgeRef, GValue, HyperEdgeRef, Key};
+use std::slice;
+
+/// A compiled path query such as `Model/edges[msg=Attack]` or
+/// `Exists/Z.has_class`.
+///
+/// The first segment names a block (or `*` for every block); a nested block
+/// is named by its path, `Model.Attack`, which takes precedence over a
+/// definition `Attack` of `[Model]`. Each following segment, separated by
+/// `/` or `.`, selects children of the current matches:
+///
+/// - `name` selects the value of the definition `name = ...` (or the bare
+///   entry `name`, or the edge `name: A -> B`) in a block or list, and the
+///   entry `name: ...` in a map
+/// - `edges` selects edge and hyperedge entries and `defs` selects leaf
+///   definitions
+/// - `*` selects every child
+///
+/// Segments may be followed by predicates in brackets: `[2]` keeps the
+/// element at that index, `[field=value]` and `[field!=value]` keep matches
+/// whose field equals (or not) the value. Edges have the fields `id`, `msg`,
+/// `from`, `to` and `kind` (`directed`, `reverse`, `bidirectional` or
+/// `undirected`); `from` and `to` of a hyperedge match any of its sources
+/// and targets. Definitions have `key`, and maps are filtered by their
+/// own entries.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Query {
+    segments: Vec<Segment>,
+}
+
+#[derive(PartialEq, Debug, Clone)]
+enum Selector {
+    Name(GValue),
+    Wildcard,
+    Edges,
+    Defs,
+}
+
+#[derive(PartialEq, Debug, Clone)]
+enum Predicate {
+    Index(usize),
+    Filter {
+        field: String,
+        value: GValue,
+        negate: bool,
+    },
+}
+
+#[derive(PartialEq, Debug, Clone)]
+struct Segment {
+    selector: Selector,
+    predicates: Vec<Predicate>,
+}
+
+struct QueryParser<'a> {
+    src: &'a str,
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl<'a> QueryParser<'a> {
+    fn error(&self, message: &str) -> Error {
+        Error::InvalidQuery {
+            query: self.src.to_string(),
+            column: self.pos + 1,
+            message: message.to_string(),
+        }
+    }
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+    fn parse_literal(&mut self) -> Result<GValue, Error> {
+        match self.peek() {
+            Some('"') => {
+                self.pos += 1;
+                let mut string = String::new();
+                loop {
+                    match self.peek() {
+                        Some('"') => {
+                            self.pos += 1;
+                            return Ok(GValue::String(string));
+                        }
+                        Some('\\') if self.chars.get(self.pos + 1).is_some() => {
+                            string.push(self.chars[self.pos + 1]);
+                            self.pos += 2;
+                        }
+                        Some(c) => {
+                            string.push(c);
+                            self.pos += 1;
+                        }
+                        None => return Err(self.error("unterminated string")),
+                    }
+                }
+            }
+            Some(c) if c.is_alphanumeric() || c == '_' || c == '-' => {
+                let start = self.pos;
+                while let Some(c) = self.peek() {
+                    let fraction = c == '.'
+                        && self.chars[start..self.pos]
+                            .iter()
+                            .all(|c| c.is_ascii_digit() || *c == '-')
+                        && self
+                            .chars
+                            .get(self.pos + 1)
+                            .is_some_and(char::is_ascii_digit);
+                    if c.is_alphanumeric() || c == '_' || c == '-' || fraction {
+                        self.pos += 1;
+                    } else {
+                        break;
+                    }
+                }
+                let text: String = self.chars[start..self.pos].iter().collect();
+                Ok(match text.as_str() {
+                    "true" => GValue::Bool(true),
+                    "false" => GValue::Bool(false),
+                    "none" => GValue::Null,
+                    _ => text
+                        .parse::<i64>()
+                        .map(GValue::Integer)
+                        .or_else(|_| text.parse::<f64>().map(GValue::Float))
+                        .unwrap_or(GValue::Symbol(text)),
+                })
+            }
+            _ => Err(self.error("expected a name")),
+        }
+    }
+    fn parse_predicate(&mut self) -> Result<Predicate, Error> {
+        let field = self.parse_literal()?;
+        if self.peek() == Some(']') {
+            return match field {
+                GValue::Integer(index) if index >= 0 => {
+                    self.pos += 1;
+                    Ok(Predicate::Index(index as usize))
+                }
+                _ => Err(self.error("expected an index or a filter")),
+            };
+        }
+        if let GValue::Integer(_) = field {
+            return Err(self.error("expected `]`"));
+        }
+        let negate = self.eat('!');
+        if !self.eat('=') {
+            return Err(self.error("expected `=` or `!=`"));
+        }
+        let value = self.parse_literal()?;
+        if !self.eat(']') {
+            return Err(self.error("expected `]`"));
+        }
+        Ok(Predicate::Filter {
+            field: field.to_string().trim_matches('"').to_string(),
+            value,
+            negate,
+        })
+    }
+    fn parse_segment(&mut self, first: bool) -> Result<Segment, Error> {
+        let selector = if self.eat('*') {
+            Selector::Wildcard
+        } else {
+            match self.parse_literal()? {
+                GValue::Symbol(name) if !first && name == "edges" => Selector::Edges,
+                GValue::Symbol(name) if !first && name == "defs" => Selector::Defs,
+                name => Selector::Name(name),
+            }
+        };
+        let mut predicates = Vec::new();
+        while self.eat('[') {
+            predicates.push(self.parse_predicate()?);
+        }
+        Ok(Segment {
+            selector,
+            predicates,
+        })
+    }
+}
+
+impl Query {
+    pub fn parse(src: &str) -> Result<Self, Error> {
+        // leading whitespace is skipped rather than trimmed, so error
+        // columns count from the start of `src`
+        let mut parser = QueryParser {
+            src,
+            chars: src.trim_end().chars().collect(),
+            pos: src.chars().count() - src.trim_start().chars().count(),
+        };
+        let mut segments = vec![parser.parse_segment(true)?];
+        while parser.eat('/') || parser.eat('.') {
+            segments.push(parser.parse_segment(false)?);
+        }
+        if parser.peek().is_some() {
+            return Err(parser.error("expected `/`, `.` or `[`"));
+        }
+        Ok(Query { segments })
+    }
+
+    /// Returns every value in `document` matched by the query.
+    pub fn select<'a>(&self, document: &'a Document) -> Vec<&'a GValue> {
+        let (first, rest) = match self.segments.split_first() {
+            Some(split) => split,
+            None => return Vec::new(),
+        };
+        let blocks: Vec<(&Block, &[Segment])> = match &first.selector {
+            Selector::Name(_) => {
+                let paths: Vec<(&Block, usize)> = document
+                    .blocks()
+                    .filter_map(|block| Some((block, block_path(block, &self.segments)?)))
+                    .collect();
+                // `Model.Attack` names the block `[Model.Attack]` if there is
+                // one, and the definition `Attack` of `[Model]` otherwise
+                let longest = paths.iter().map(|(_, len)| *len).max();
+                paths
+                    .into_iter()
+                    .filter(|(_, len)| Some(*len) == longest)
+                    .map(|(block, len)| (block, &self.segments[len..]))
+                    .collect()
+            }
+            _ => document.blocks().map(|block| (block, rest)).collect(),
+        };
+        let mut matches = Vec::new();
+        for (block, rest) in blocks {
+            let entries: Vec<&GValue> = block.values().collect();
+            matches.extend(select_all(&entries, rest));
+        }
+        matches
+    }
+
+    /// Applies the query to the entries of a single value; the first
+    /// segment selects children of `value` like every other segment.
+    pub fn select_value<'a>(&self, value: &'a GValue) -> Vec<&'a GValue> {
+        let children = match value {
+            GValue::Vec(values) => values.iter().collect(),
+            _ => vec![value],
+        };
+        select_all(&children, &self.segments)
+    }
+}
+
+/// Runs `query` against `document`.
+pub fn query<'a>(document: &'a Document, query: &str) -> Result<Vec<&'a GValue>, Error> {
+    Ok(Query::parse(query)?.select(document))
+}
+
+fn text(value: &GValue) -> Option<&str> {
+    match value {
+        GValue::Symbol(name) | GValue::String(name) => Some(name),
+        _ => None,
+    }
+}
+
+/// The number of leading segments spelling the dotted path of `block`, so
+/// that `Model.Attack/edges` selects the edges of `[Model.Attack]`.
+fn block_path(block: &Block, segments: &[Segment]) -> Option<usize> {
+    let mut len = 0;
+    for name in block.path() {
+        match &segments.get(len)?.selector {
+            Selector::Name(segment) if text(segment) == Some(name) => len += 1,
+            _ => return None,
+        }
+    }
+    Some(len)
+}
+
+fn key_matches(query: &GValue, key: &GValue) -> bool {
+    match text(query) {
+        Some(name) => name.matches_key(key),
+        None => query == key,
+    }
+}
+
+fn select_all<'a>(entries: &[&'a GValue], segments: &[Segment]) -> Vec<&'a GValue> {
+    let (segment, rest) = match segments.split_first() {
+        Some(split) => split,
+        None => return entries.to_vec(),
+    };
+    let mut matches: Vec<&GValue> = match &segment.selector {
+        Selector::Wildcard => entries.to_vec(),
+        Selector::Edges => entries
+            .iter()
+            .copied()
+            .filter(|entry| {
+                EdgeRef::from_entry(entry).is_some() || HyperEdgeRef::from_entry(entry).is_some()
+            })
+            .collect(),
+        Selector::Defs => entries
+            .iter()
+            .copied()
+            .filter(|entry| leaf_def(entry).is_some())
+            .collect(),
+        Selector::Name(name) => entries
+            .iter()
+            .filter_map(|entry| match (leaf_def(entry), entry) {
+                (Some((key, value)), _) if key_matches(name, key) => Some(value),
+                (None, GValue::Named { id, .. })
+                    if key_matches(name, &GValue::Symbol(id.to_string())) =>
+                {
+                    Some(*entry)
+                }
+                (None, _) if key_matches(name, entry) => Some(*entry),
+                _ => None,
+            })
+            .collect(),
+    };
+    filter(&mut matches, &segment.predicates);
+    matches
+        .into_iter()
+        .flat_map(|value| select_children(value, rest))
+        .collect()
+}
+
+fn select_children<'a>(value: &'a GValue, segments: &[Segment]) -> Vec<&'a GValue> {
+    let (segment, rest) = match segments.split_first() {
+        Some(split) => split,
+        None => return vec![value],
+    };
+    let map = match value {
+        GValue::Map(map) => map,
+        GValue::Vec(values) | GValue::Message(_, values) => {
+            let entries: Vec<&GValue> = values.iter().collect();
+            return select_all(&entries, segments);
+        }
+        _ => return Vec::new(),
+    };
+    let mut matches: Vec<&GValue> = match &segment.selector {
+        Selector::Wildcard => map.values().collect(),
+        Selector::Name(name) => map
+            .iter()
+            .filter(|(key, _)| key_matches(name, key))
+            .map(|(_, value)| value)
+            .collect(),
+        Selector::Edges | Selector::Defs => Vec::new(),
+    };
+    filter(&mut matches, &segment.predicates);
+    matches
+        .into_iter()
+        .flat_map(|value| select_children(value, rest))
+        .collect()
+}
+
+fn filter(matches: &mut Vec<&GValue>, predicates: &[Predicate]) {
+    for predicate in predicates {
+        match predicate {
+            Predicate::Index(index) => {
+                let selected = matches.get(*index).copied();
+                *matches = selected.into_iter().collect();
+            }
+            Predicate::Filter {
+                field,
+                value,
+                negate,
+            } => matches.retain(|entry| field_matches(entry, field, value) != *negate),
+        }
+    }
+}
+
+/// Reads an edge or hyperedge entry, an edge having a single source and
+/// target.
+fn edge_fields(entry: &GValue) -> Option<HyperEdgeRef<'_>> {
+    match EdgeRef::from_entry(entry) {
+        Some(edge) => Some(HyperEdgeRef {
+            id: edge.id,
+            sources: slice::from_ref(edge.from),
+            targets: slice::from_ref(edge.to),
+            kind: edge.kind,
+            stmt: edge.stmt,
+        }),
+        None => HyperEdgeRef::from_entry(entry),
+    }
+}
+
+fn field_matches(entry: &GValue, field: &str, expected: &GValue) -> bool {
+    let symbol_matches = |name: &str| key_matches(expected, &GValue::Symbol(name.to_string()));
+    let actual = match (edge_fields(entry), leaf_def(entry), entry) {
+        (Some(edge), _, _) => {
+            return match field {
+                "id" => edge.id.is_some_and(symbol_matches),
+                "msg" => edge.message().is_some_and(symbol_matches),
+                "from" => edge.sources.iter().any(|from| key_matches(expected, from)),
+                "to" => edge.targets.iter().any(|to| key_matches(expected, to)),
+                "kind" => symbol_matches(edge.kind.name()),
+                _ => false,
+            }
+        }
+        (_, Some((key, value)), _) => match field {
+            "key" => Some(key),
+            "value" => Some(value),
+            _ => None,
+        },
+        (_, _, GValue::Map(map)) => map.get(field),
+        _ => None,
+    };
+    actual.is_some_and(|actual| key_matches(expected, actual))
+}
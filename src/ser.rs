@@ -0,0 +1,739 @@
+//! Serialize Rust data structures into GMML with serde.
+//!
+//! Values are first converted into a `GValue`, and a struct or map of blocks
+//! is then written as a document. Inside a block, map entries become
+//! definitions (`key = value`, or a bare `key` for `none`) and tuple keys
+//! `(from, to)` become edges; sequence elements become entries, where a
+//! pair of names `(from, to)` is an edge, `(key, value)` a definition and
+//! `((from, to), stmt)` an edge definition. A block whose content can not
+//! be written as entries, such as `true` or a list of structs, holds the
+//! single definition `value = ...`. Unit variants are written as symbols
+//! and other variants as messages, e.g. `Move(1, 2)`.
+
+use crate::document::{Block, Document};
+use crate::error::Error;
+use crate::value::{is_identifier, EdgeKind, GValue, Map};
+use serde::ser::{self, Serialize, SerializeMap, SerializeSeq, SerializeTuple};
+use std::convert::TryFrom;
+use std::fmt;
+
+const STRING: &str = "$gmml::String";
+const SYMBOL: &str = "$gmml::Symbol";
+const MESSAGE: &str = "$gmml::Message";
+const EDGE: &str = "$gmml::Edge";
+const PAIR: &str = "$gmml::Pair";
+const HYPER_EDGE: &str = "$gmml::HyperEdge";
+const NAMED: &str = "$gmml::Named";
+const PATH: &str = "$gmml::Path";
+const BLOCK: &str = "$gmml::Block";
+
+/// The key of the definition holding a block that can not be written as
+/// entries, `value = true`.
+pub(crate) const VALUE: &str = "value";
+
+impl ser::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error::Serialize {
+            message: msg.to_string(),
+        }
+    }
+}
+
+fn error(message: String) -> Error {
+    Error::Serialize { message }
+}
+
+/// Serializes `value` and writes it as a GMML document.
+pub fn to_string<T: Serialize + ?Sized>(value: &T) -> Result<String, Error> {
+    write_document(&to_document(value)?)
+}
+
+/// Serializes `value` into a document, one block per field or map entry. A
+/// scalar field becomes a block holding just that value, `None` an empty
+/// block, and other content that can not be entries, such as `true`,
+/// `Heal(2)` or a list of structs, the definition `value = ...`; all of
+/// them read back with `from_str`.
+pub fn to_document<T: Serialize + ?Sized>(value: &T) -> Result<Document, Error> {
+    let blocks = match to_value(value)? {
+        GValue::Map(blocks) => blocks,
+        value => {
+            return Err(error(format!(
+                "a document must be a struct or map of blocks, found {}",
+                value.kind()
+            )))
+        }
+    };
+    let mut document = Document::new();
+    for (name, content) in blocks {
+        let mut block = match name {
+            GValue::String(name) | GValue::Symbol(name) => Block::new(&name),
+            name => {
+                return Err(error(format!(
+                    "block names must be strings, found {}",
+                    name.kind()
+                )))
+            }
+        };
+        for entry in block_entries(content)? {
+            block.push(entry);
+        }
+        document.push(block);
+    }
+    Ok(document)
+}
+
+/// The entries of a block holding `content`. Content that can not be
+/// written as entries, such as `true`, a message or a list of structs, is
+/// written as the single definition `value = ...`.
+fn block_entries(content: GValue) -> Result<Vec<GValue>, Error> {
+    match content {
+        GValue::Map(map) => map
+            .into_iter()
+            .map(|(key, value)| {
+                let target = match key {
+                    GValue::Vec(ends) => edge(ends)?,
+                    key => key,
+                };
+                Ok(match value {
+                    GValue::Null => target,
+                    value => GValue::Pair(Box::new(target), Box::new(value)),
+                })
+            })
+            .collect(),
+        GValue::Null => Ok(Vec::new()),
+        content => {
+            let entries = match &content {
+                GValue::Vec(entries) => entries.iter().cloned().map(block_entry).collect(),
+                entry => Ok(vec![entry.clone()]),
+            };
+            match entries {
+                Ok(entries) if entries.iter().all(|entry| check_entry(entry).is_ok()) => {
+                    Ok(entries)
+                }
+                _ => Ok(vec![GValue::Pair(
+                    Box::new(GValue::Symbol(VALUE.to_string())),
+                    Box::new(content),
+                )]),
+            }
+        }
+    }
+}
+
+/// Serializes `value` into a `GValue`.
+pub fn to_value<T: Serialize + ?Sized>(value: &T) -> Result<GValue, Error> {
+    value.serialize(Serializer::new())
+}
+
+/// Turns string keys that are ASCII identifiers into symbols, so they are
+/// written unquoted; other keys such as `"文字列"` stay quoted.
+fn key(value: GValue) -> GValue {
+    match value {
+        GValue::String(name) if name.is_ascii() && is_identifier(&name) => GValue::Symbol(name),
+        value => value,
+    }
+}
+
+fn edge(ends: Vec<GValue>) -> Result<GValue, Error> {
+    match <[GValue; 2]>::try_from(ends) {
+        Ok([from, to]) => Ok(GValue::Edge(
+            Box::new(key(from)),
+            Box::new(key(to)),
+            EdgeKind::Directed,
+        )),
+        Err(ends) => Err(error(format!(
+            "an edge needs 2 endpoints, found {}",
+            ends.len()
+        ))),
+    }
+}
+
+/// Reads the operator marking the kind of a serialized edge, `->` if none.
+fn edge_kind(operator: Option<&GValue>) -> Result<EdgeKind, Error> {
+    match operator {
+        Some(GValue::String(operator)) => EdgeKind::from_operator(operator)
+            .ok_or_else(|| error(format!("invalid edge operator `{}`", operator))),
+        Some(_) => Err(error("invalid edge operator".to_string())),
+        None => Ok(EdgeKind::Directed),
+    }
+}
+
+/// Interprets a sequence element of a block: lists can not be block
+/// entries, so `(from, to)` is read as an edge and `((from, to), stmt)` as
+/// an edge definition.
+fn block_entry(entry: GValue) -> Result<GValue, Error> {
+    match entry {
+        GValue::Vec(mut items) if items.len() == 2 => match &items[0] {
+            GValue::Vec(_) => {
+                let stmt = items.pop().unwrap();
+                let target = match items.pop().unwrap() {
+                    GValue::Vec(ends) => edge(ends)?,
+                    _ => unreachable!(),
+                };
+                Ok(GValue::Pair(Box::new(target), Box::new(stmt)))
+            }
+            _ if is_name(&items[0]) && is_name(&items[1]) => edge(items),
+            _ => {
+                let stmt = items.pop().unwrap();
+                let target = key(items.pop().unwrap());
+                Ok(GValue::Pair(Box::new(target), Box::new(stmt)))
+            }
+        },
+        entry => Ok(entry),
+    }
+}
+
+fn is_name(value: &GValue) -> bool {
+    matches!(
+        value,
+        GValue::String(_) | GValue::Symbol(_) | GValue::Path(_)
+    )
+}
+
+pub(crate) fn check_name(name: &str) -> Result<(), Error> {
+    if name.is_empty() || name.contains(['`', '\n', '\r']) {
+        return Err(error(format!("`{}` can not be written as a name", name)));
+    }
+    Ok(())
+}
+
+/// The header name of a block, a dotted path for a nested block such as
+/// `Model.Attack`.
+pub(crate) fn block_name(name: &str) -> Result<GValue, Error> {
+    let names: Vec<String> = name.split('.').map(str::to_string).collect();
+    names.iter().try_for_each(|name| check_name(name))?;
+    Ok(match names.len() {
+        1 => GValue::Symbol(name.to_string()),
+        _ => GValue::Path(names),
+    })
+}
+
+pub(crate) fn check_key(key: &GValue) -> Result<(), Error> {
+    match key {
+        GValue::Symbol(name) => check_name(name),
+        GValue::Path(names) if names.len() > 1 => {
+            names.iter().try_for_each(|name| check_name(name))
+        }
+        GValue::String(_) | GValue::Integer(_) => Ok(()),
+        GValue::Float(number) if number.is_finite() => Ok(()),
+        GValue::Float(number) => Err(error(format!("can not write the float {}", number))),
+        key => Err(error(format!("{} can not be written as a key", key.kind()))),
+    }
+}
+
+pub(crate) fn check_value(value: &GValue) -> Result<(), Error> {
+    match value {
+        GValue::String(_) | GValue::Integer(_) | GValue::Bool(_) | GValue::Null => Ok(()),
+        GValue::Float(_) | GValue::Symbol(_) | GValue::Path(_) => check_key(value),
+        GValue::Message(name, args) => {
+            check_name(name)?;
+            args.iter().try_for_each(check_value)
+        }
+        GValue::Vec(values) => values.iter().try_for_each(check_value),
+        GValue::Map(map) => map.iter().try_for_each(|(key, value)| {
+            check_key(key)?;
+            check_value(value)
+        }),
+        GValue::Edge(..) | GValue::HyperEdge { .. } | GValue::Named { .. } | GValue::Pair(..) => {
+            Err(error(format!(
+                "{} can only be written as a block entry",
+                value.kind()
+            )))
+        }
+    }
+}
+
+/// Checks an end of an edge, a key or a group of keys `(A, B)`.
+fn check_endpoint(value: &GValue) -> Result<(), Error> {
+    match value {
+        GValue::Vec(keys) if keys.is_empty() => {
+            Err(error("an edge group can not be empty".to_string()))
+        }
+        GValue::Vec(keys) => keys.iter().try_for_each(check_key),
+        key => check_key(key),
+    }
+}
+
+pub(crate) fn check_entry(entry: &GValue) -> Result<(), Error> {
+    match entry {
+        GValue::Edge(from, to, _) => {
+            check_endpoint(from)?;
+            check_endpoint(to)
+        }
+        GValue::HyperEdge {
+            sources, targets, ..
+        } => {
+            if sources.is_empty() || targets.is_empty() || sources.len() + targets.len() < 3 {
+                return Err(error(
+                    "a hyperedge needs a node on each side and at least 3 nodes".to_string(),
+                ));
+            }
+            sources.iter().chain(targets).try_for_each(check_endpoint)
+        }
+        GValue::Named { id, edge } => {
+            check_name(id)?;
+            match &**edge {
+                GValue::Pair(target, _) if target.is_edge() => check_entry(edge),
+                edge if edge.is_edge() => check_entry(edge),
+                edge => Err(error(format!("a {} can not have an edge id", edge.kind()))),
+            }
+        }
+        GValue::Pair(target, stmt) => {
+            if target.is_edge() {
+                check_entry(target)?;
+            } else {
+                check_key(target)?;
+            }
+            check_value(stmt)
+        }
+        GValue::Bool(_) | GValue::Null => Err(error(format!(
+            "`{}` can not be written as a block entry",
+            entry
+        ))),
+        entry => check_key(entry),
+    }
+}
+
+/// Writes `document` in GMML syntax, failing if it holds a value that can
+/// not be written, e.g. a list as a block entry or a non-finite float.
+pub fn write_document(document: &Document) -> Result<String, Error> {
+    let mut out = String::new();
+    for (i, block) in document.blocks().enumerate() {
+        let name = block_name(&block.name)?;
+        if i > 0 {
+            out.push('\n');
+        }
+        out.push_str(&format!("[{}]\n", name));
+        for entry in block.values() {
+            check_entry(entry)?;
+            out.push_str(&format!("{}\n", entry));
+        }
+    }
+    Ok(out)
+}
+
+impl Serialize for GValue {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            GValue::String(string) => serializer.serialize_newtype_struct(STRING, string),
+            GValue::Integer(number) => serializer.serialize_i64(*number),
+            GValue::Float(number) => serializer.serialize_f64(*number),
+            GValue::Bool(value) => serializer.serialize_bool(*value),
+            GValue::Null => serializer.serialize_unit(),
+            GValue::Symbol(name) => serializer.serialize_newtype_struct(SYMBOL, name),
+            GValue::Path(names) => serializer.serialize_newtype_struct(PATH, names),
+            GValue::Message(name, args) => {
+                serializer.serialize_newtype_struct(MESSAGE, &(name, args))
+            }
+            GValue::Edge(from, to, EdgeKind::Directed) => {
+                serializer.serialize_newtype_struct(EDGE, &(from, to))
+            }
+            GValue::Edge(from, to, kind) => {
+                serializer.serialize_newtype_struct(EDGE, &(from, to, kind.as_str()))
+            }
+            GValue::HyperEdge {
+                sources,
+                targets,
+                kind,
+            } => {
+                serializer.serialize_newtype_struct(HYPER_EDGE, &(sources, targets, kind.as_str()))
+            }
+            GValue::Named { id, edge } => serializer.serialize_newtype_struct(NAMED, &(id, edge)),
+            GValue::Pair(target, stmt) => {
+                serializer.serialize_newtype_struct(PAIR, &(target, stmt))
+            }
+            GValue::Vec(values) => values.serialize(serializer),
+            GValue::Map(map) => map.serialize(serializer),
+        }
+    }
+}
+
+impl Serialize for Map {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.len()))?;
+        for (key, value) in self {
+            map.serialize_entry(key, value)?;
+        }
+        map.end()
+    }
+}
+
+impl Serialize for Block {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_newtype_struct(BLOCK, &self.values().collect::<Vec<_>>())
+    }
+}
+
+impl Serialize for Document {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.len()))?;
+        for block in self {
+            map.serialize_entry(&block.name, block)?;
+        }
+        map.end()
+    }
+}
+
+/// Serializes values into a `GValue`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Serializer {
+    key: bool,
+}
+
+impl Serializer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl ser::Serializer for Serializer {
+    type Ok = GValue;
+    type Error = Error;
+    type SerializeSeq = SeqSerializer;
+    type SerializeTuple = SeqSerializer;
+    type SerializeTupleStruct = SeqSerializer;
+    type SerializeTupleVariant = SeqSerializer;
+    type SerializeMap = MapSerializer;
+    type SerializeStruct = MapSerializer;
+    type SerializeStructVariant = MapSerializer;
+
+    fn serialize_bool(self, v: bool) -> Result<GValue, Error> {
+        Ok(GValue::Bool(v))
+    }
+    fn serialize_i8(self, v: i8) -> Result<GValue, Error> {
+        self.serialize_i64(v.into())
+    }
+    fn serialize_i16(self, v: i16) -> Result<GValue, Error> {
+        self.serialize_i64(v.into())
+    }
+    fn serialize_i32(self, v: i32) -> Result<GValue, Error> {
+        self.serialize_i64(v.into())
+    }
+    fn serialize_i64(self, v: i64) -> Result<GValue, Error> {
+        Ok(GValue::Integer(v))
+    }
+    fn serialize_i128(self, v: i128) -> Result<GValue, Error> {
+        i64::try_from(v)
+            .map(GValue::Integer)
+            .map_err(|_| error(format!("integer {} is out of range", v)))
+    }
+    fn serialize_u8(self, v: u8) -> Result<GValue, Error> {
+        self.serialize_i64(v.into())
+    }
+    fn serialize_u16(self, v: u16) -> Result<GValue, Error> {
+        self.serialize_i64(v.into())
+    }
+    fn serialize_u32(self, v: u32) -> Result<GValue, Error> {
+        self.serialize_i64(v.into())
+    }
+    fn serialize_u64(self, v: u64) -> Result<GValue, Error> {
+        self.serialize_i128(v.into())
+    }
+    fn serialize_u128(self, v: u128) -> Result<GValue, Error> {
+        i64::try_from(v)
+            .map(GValue::Integer)
+            .map_err(|_| error(format!("integer {} is out of range", v)))
+    }
+    fn serialize_f32(self, v: f32) -> Result<GValue, Error> {
+        self.serialize_f64(v.into())
+    }
+    fn serialize_f64(self, v: f64) -> Result<GValue, Error> {
+        Ok(GValue::Float(v))
+    }
+    fn serialize_char(self, v: char) -> Result<GValue, Error> {
+        Ok(GValue::String(v.to_string()))
+    }
+    fn serialize_str(self, v: &str) -> Result<GValue, Error> {
+        let string = GValue::String(v.to_string());
+        Ok(if self.key { key(string) } else { string })
+    }
+    fn serialize_bytes(self, v: &[u8]) -> Result<GValue, Error> {
+        Ok(GValue::Vec(
+            v.iter()
+                .map(|byte| GValue::Integer((*byte).into()))
+                .collect(),
+        ))
+    }
+    fn serialize_none(self) -> Result<GValue, Error> {
+        Ok(GValue::Null)
+    }
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<GValue, Error> {
+        value.serialize(self)
+    }
+    fn serialize_unit(self) -> Result<GValue, Error> {
+        Ok(GValue::Null)
+    }
+    fn serialize_unit_struct(self, _: &'static str) -> Result<GValue, Error> {
+        Ok(GValue::Null)
+    }
+    fn serialize_unit_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+    ) -> Result<GValue, Error> {
+        Ok(GValue::Symbol(variant.to_string()))
+    }
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<GValue, Error> {
+        if !name.starts_with("$gmml::") {
+            return value.serialize(self);
+        }
+        Ok(match (name, value.serialize(Serializer::new())?) {
+            (SYMBOL, GValue::String(name)) => GValue::Symbol(name),
+            // the entries of a block are written as they are, never as
+            // `value = ...`, so they are checked here
+            (BLOCK, GValue::Vec(entries)) => {
+                entries.iter().try_for_each(check_entry)?;
+                GValue::Vec(entries)
+            }
+            (PATH, GValue::Vec(names)) => GValue::Path(
+                names
+                    .into_iter()
+                    .map(|name| match name {
+                        GValue::String(name) => Ok(name),
+                        _ => Err(error("invalid path".to_string())),
+                    })
+                    .collect::<Result<_, _>>()?,
+            ),
+            (MESSAGE, GValue::Vec(mut parts)) if parts.len() == 2 => {
+                match (parts.remove(0), parts.remove(0)) {
+                    (GValue::String(name), GValue::Vec(args)) => GValue::Message(name, args),
+                    _ => return Err(error("invalid message".to_string())),
+                }
+            }
+            (EDGE, GValue::Vec(mut parts)) if parts.len() == 2 || parts.len() == 3 => {
+                let kind = edge_kind(parts.get(2))?;
+                parts.truncate(2);
+                let to = parts.pop().unwrap();
+                GValue::Edge(Box::new(parts.pop().unwrap()), Box::new(to), kind)
+            }
+            (HYPER_EDGE, GValue::Vec(mut parts)) if parts.len() == 3 => {
+                let kind = edge_kind(parts.get(2))?;
+                parts.truncate(2);
+                match (parts.remove(0), parts.remove(0)) {
+                    (GValue::Vec(sources), GValue::Vec(targets)) => GValue::HyperEdge {
+                        sources,
+                        targets,
+                        kind,
+                    },
+                    _ => return Err(error("invalid hyperedge".to_string())),
+                }
+            }
+            (NAMED, GValue::Vec(mut parts)) if parts.len() == 2 => {
+                match (parts.remove(0), parts.remove(0)) {
+                    (GValue::String(id), edge) => GValue::Named {
+                        id,
+                        edge: Box::new(edge),
+                    },
+                    _ => return Err(error("invalid edge id".to_string())),
+                }
+            }
+            (PAIR, GValue::Vec(mut parts)) if parts.len() == 2 => {
+                let stmt = parts.pop().unwrap();
+                GValue::Pair(Box::new(parts.pop().unwrap()), Box::new(stmt))
+            }
+            (_, value) => value,
+        })
+    }
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<GValue, Error> {
+        Ok(GValue::Message(
+            variant.to_string(),
+            vec![value.serialize(Serializer::new())?],
+        ))
+    }
+    fn serialize_seq(self, len: Option<usize>) -> Result<SeqSerializer, Error> {
+        Ok(SeqSerializer {
+            serializer: self,
+            name: None,
+            values: Vec::with_capacity(len.unwrap_or(0)),
+        })
+    }
+    fn serialize_tuple(self, len: usize) -> Result<SeqSerializer, Error> {
+        self.serialize_seq(Some(len))
+    }
+    fn serialize_tuple_struct(self, _: &'static str, len: usize) -> Result<SeqSerializer, Error> {
+        self.serialize_seq(Some(len))
+    }
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SeqSerializer, Error> {
+        Ok(SeqSerializer {
+            serializer: self,
+            name: Some(variant.to_string()),
+            values: Vec::with_capacity(len),
+        })
+    }
+    fn serialize_map(self, _: Option<usize>) -> Result<MapSerializer, Error> {
+        Ok(MapSerializer {
+            name: None,
+            map: Map::new(),
+            key: None,
+        })
+    }
+    fn serialize_struct(self, _: &'static str, len: usize) -> Result<MapSerializer, Error> {
+        self.serialize_map(Some(len))
+    }
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        _: usize,
+    ) -> Result<MapSerializer, Error> {
+        Ok(MapSerializer {
+            name: Some(variant.to_string()),
+            map: Map::new(),
+            key: None,
+        })
+    }
+}
+
+/// Collects a sequence, or the arguments of a tuple variant.
+pub struct SeqSerializer {
+    serializer: Serializer,
+    name: Option<String>,
+    values: Vec<GValue>,
+}
+
+impl SeqSerializer {
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.values.push(value.serialize(self.serializer)?);
+        Ok(())
+    }
+    fn finish(self) -> GValue {
+        match self.name {
+            Some(name) => GValue::Message(name, self.values),
+            None => GValue::Vec(self.values),
+        }
+    }
+}
+
+impl SerializeSeq for SeqSerializer {
+    type Ok = GValue;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+    fn end(self) -> Result<GValue, Error> {
+        Ok(self.finish())
+    }
+}
+
+impl SerializeTuple for SeqSerializer {
+    type Ok = GValue;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+    fn end(self) -> Result<GValue, Error> {
+        Ok(self.finish())
+    }
+}
+
+impl ser::SerializeTupleStruct for SeqSerializer {
+    type Ok = GValue;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+    fn end(self) -> Result<GValue, Error> {
+        Ok(self.finish())
+    }
+}
+
+impl ser::SerializeTupleVariant for SeqSerializer {
+    type Ok = GValue;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+    fn end(self) -> Result<GValue, Error> {
+        Ok(self.finish())
+    }
+}
+
+/// Collects a map or struct, or the fields of a struct variant.
+pub struct MapSerializer {
+    name: Option<String>,
+    map: Map,
+    key: Option<GValue>,
+}
+
+impl MapSerializer {
+    fn finish(self) -> GValue {
+        match self.name {
+            Some(name) => GValue::Message(name, vec![GValue::Map(self.map)]),
+            None => GValue::Map(self.map),
+        }
+    }
+}
+
+impl SerializeMap for MapSerializer {
+    type Ok = GValue;
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+        self.key = Some(key.serialize(Serializer { key: true })?);
+        Ok(())
+    }
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        let key = match self.key.take() {
+            Some(key) => key,
+            None => return Err(error("map value without a key".to_string())),
+        };
+        self.map.insert(key, value.serialize(Serializer::new())?);
+        Ok(())
+    }
+    fn end(self) -> Result<GValue, Error> {
+        Ok(self.finish())
+    }
+}
+
+impl ser::SerializeStruct for MapSerializer {
+    type Ok = GValue;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.serialize_entry(name, value)
+    }
+    fn end(self) -> Result<GValue, Error> {
+        Ok(self.finish())
+    }
+}
+
+impl ser::SerializeStructVariant for MapSerializer {
+    type Ok = GValue;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.serialize_entry(name, value)
+    }
+    fn end(self) -> Result<GValue, Error> {
+        Ok(self.finish())
+    }
+}
@@ -0,0 +1,598 @@
+use crate::error::Error;
+use std::convert::TryFrom;
+use std::fmt;
+use std::iter::FromIterator;
+use std::slice;
+use std::vec;
+
+#[derive(PartialEq, Debug, Clone)]
+pub enum GValue {
+    String(String),
+    Integer(i64),
+    Float(f64),
+    Bool(bool),
+    Null,
+    Symbol(String),
+    /// A dotted path `Router.eth0` addressing a part of a node.
+    Path(Vec<String>),
+    Message(String, Vec<GValue>),
+    /// An edge `from -> to`. The ends follow the direction of the edge, so
+    /// `A <- B` is `Edge(B, A, EdgeKind::Reverse)`.
+    Edge(Box<GValue>, Box<GValue>, EdgeKind),
+    /// An edge joining several nodes, e.g. `X + Y -> Z`. `sources` and
+    /// `targets` follow the direction of the edge, so `Z <- X + Y` has `X`
+    /// and `Y` as `sources`.
+    HyperEdge {
+        sources: Vec<GValue>,
+        targets: Vec<GValue>,
+        kind: EdgeKind,
+    },
+    /// An edge or hyperedge entry with an id, `e1: Y -> Z : Attack()`.
+    /// `edge` is the edge, or a `Pair` of the edge and its statement.
+    Named {
+        id: String,
+        edge: Box<GValue>,
+    },
+    Vec(Vec<GValue>),
+    Map(Map),
+    Pair(Box<GValue>, Box<GValue>),
+}
+
+/// The operator of an edge.
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+pub enum EdgeKind {
+    /// `A -> B`
+    Directed,
+    /// `A <- B`, the same as `B -> A`
+    Reverse,
+    /// `A <-> B`
+    Bidirectional,
+    /// `A -- B`
+    Undirected,
+}
+
+impl EdgeKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EdgeKind::Directed => "->",
+            EdgeKind::Reverse => "<-",
+            EdgeKind::Bidirectional => "<->",
+            EdgeKind::Undirected => "--",
+        }
+    }
+    pub fn from_operator(operator: &str) -> Option<Self> {
+        Some(match operator {
+            "->" => EdgeKind::Directed,
+            "<-" => EdgeKind::Reverse,
+            "<->" => EdgeKind::Bidirectional,
+            "--" => EdgeKind::Undirected,
+            _ => return None,
+        })
+    }
+    /// A lowercase name for the kind, e.g. `undirected`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            EdgeKind::Directed => "directed",
+            EdgeKind::Reverse => "reverse",
+            EdgeKind::Bidirectional => "bidirectional",
+            EdgeKind::Undirected => "undirected",
+        }
+    }
+    /// Whether the edge points one way, `->` or `<-`.
+    pub fn is_directed(&self) -> bool {
+        matches!(self, EdgeKind::Directed | EdgeKind::Reverse)
+    }
+}
+
+impl fmt::Display for EdgeKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// An edge entry of a block, with the statement after `:` if any.
+///
+/// `from` and `to` follow the direction of the edge, so `A <- B` has `B` as
+/// `from`; edges without a direction keep the order they are written in.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct EdgeRef<'a> {
+    pub id: Option<&'a str>,
+    pub from: &'a GValue,
+    pub to: &'a GValue,
+    pub kind: EdgeKind,
+    pub stmt: Option<&'a GValue>,
+}
+
+/// Splits an edge entry into its id, the edge and its statement.
+fn edge_parts(entry: &GValue) -> (Option<&str>, &GValue, Option<&GValue>) {
+    let (id, entry) = match entry {
+        GValue::Named { id, edge } => (Some(id.as_str()), &**edge),
+        entry => (None, entry),
+    };
+    match entry {
+        GValue::Pair(target, stmt) => (id, target, Some(stmt)),
+        entry => (id, entry, None),
+    }
+}
+
+impl<'a> EdgeRef<'a> {
+    /// Interprets a block entry as an edge, with or without an id and a
+    /// statement.
+    pub fn from_entry(entry: &'a GValue) -> Option<Self> {
+        let (id, target, stmt) = edge_parts(entry);
+        match target {
+            GValue::Edge(from, to, kind) => Some(EdgeRef {
+                id,
+                from,
+                to,
+                kind: *kind,
+                stmt,
+            }),
+            _ => None,
+        }
+    }
+    /// The message name of the statement, e.g. `Attack` for `: Attack()`.
+    pub fn message(&self) -> Option<&'a str> {
+        message_name(self.stmt)
+    }
+}
+
+/// A hyperedge entry of a block, with the statement after `:` if any.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct HyperEdgeRef<'a> {
+    pub id: Option<&'a str>,
+    pub sources: &'a [GValue],
+    pub targets: &'a [GValue],
+    pub kind: EdgeKind,
+    pub stmt: Option<&'a GValue>,
+}
+
+impl<'a> HyperEdgeRef<'a> {
+    /// Interprets a block entry as a hyperedge, with or without an id and a
+    /// statement.
+    pub fn from_entry(entry: &'a GValue) -> Option<Self> {
+        let (id, target, stmt) = edge_parts(entry);
+        match target {
+            GValue::HyperEdge {
+                sources,
+                targets,
+                kind,
+            } => Some(HyperEdgeRef {
+                id,
+                sources,
+                targets,
+                kind: *kind,
+                stmt,
+            }),
+            _ => None,
+        }
+    }
+    /// The message name of the statement, e.g. `Attack` for `: Attack()`.
+    pub fn message(&self) -> Option<&'a str> {
+        message_name(self.stmt)
+    }
+}
+
+/// The message name of an edge statement, e.g. `Attack` for `Attack()`.
+fn message_name(stmt: Option<&GValue>) -> Option<&str> {
+    match stmt {
+        Some(GValue::Message(name, _)) | Some(GValue::Symbol(name)) => Some(name),
+        _ => None,
+    }
+}
+
+/// Interprets a block entry as a leaf definition `key = value`.
+pub fn leaf_def(entry: &GValue) -> Option<(&GValue, &GValue)> {
+    match entry {
+        GValue::Pair(target, stmt) if !target.is_edge() => Some((target, stmt)),
+        _ => None,
+    }
+}
+
+impl GValue {
+    /// Whether the value is an edge or a hyperedge.
+    pub fn is_edge(&self) -> bool {
+        matches!(self, GValue::Edge(..) | GValue::HyperEdge { .. })
+    }
+    pub fn kind(&self) -> &'static str {
+        match self {
+            GValue::String(_) => "string",
+            GValue::Integer(_) => "integer",
+            GValue::Float(_) => "float",
+            GValue::Bool(_) => "bool",
+            GValue::Null => "none",
+            GValue::Symbol(_) => "symbol",
+            GValue::Path(_) => "path",
+            GValue::Message(..) => "message",
+            GValue::Edge(..) => "edge",
+            GValue::HyperEdge { .. } => "hyperedge",
+            GValue::Named { .. } => "named edge",
+            GValue::Vec(_) => "list",
+            GValue::Map(_) => "map",
+            GValue::Pair(..) => "definition",
+        }
+    }
+    fn type_error(&self, expected: &'static str) -> Error {
+        Error::Type {
+            expected,
+            found: self.kind(),
+        }
+    }
+    pub fn is_null(&self) -> bool {
+        *self == GValue::Null
+    }
+    /// Returns the text of a string or a symbol.
+    pub fn as_str(&self) -> Result<&str, Error> {
+        match self {
+            GValue::String(string) | GValue::Symbol(string) => Ok(string),
+            _ => Err(self.type_error("string or symbol")),
+        }
+    }
+    pub fn as_symbol(&self) -> Result<&str, Error> {
+        match self {
+            GValue::Symbol(name) => Ok(name),
+            _ => Err(self.type_error("symbol")),
+        }
+    }
+    pub fn as_i64(&self) -> Result<i64, Error> {
+        match self {
+            GValue::Integer(number) => Ok(*number),
+            _ => Err(self.type_error("integer")),
+        }
+    }
+    /// Returns a float, converting integers.
+    pub fn as_f64(&self) -> Result<f64, Error> {
+        match self {
+            GValue::Float(number) => Ok(*number),
+            GValue::Integer(number) => Ok(*number as f64),
+            _ => Err(self.type_error("number")),
+        }
+    }
+    pub fn as_bool(&self) -> Result<bool, Error> {
+        match self {
+            GValue::Bool(value) => Ok(*value),
+            _ => Err(self.type_error("bool")),
+        }
+    }
+    pub fn as_vec(&self) -> Result<&[GValue], Error> {
+        match self {
+            GValue::Vec(values) => Ok(values),
+            _ => Err(self.type_error("list")),
+        }
+    }
+    pub fn as_map(&self) -> Result<&Map, Error> {
+        match self {
+            GValue::Map(map) => Ok(map),
+            _ => Err(self.type_error("map")),
+        }
+    }
+    pub fn as_message(&self) -> Result<(&str, &[GValue]), Error> {
+        match self {
+            GValue::Message(name, args) => Ok((name, args)),
+            _ => Err(self.type_error("message")),
+        }
+    }
+    pub fn as_edge(&self) -> Result<EdgeRef<'_>, Error> {
+        EdgeRef::from_entry(self).ok_or_else(|| self.type_error("edge"))
+    }
+    pub fn as_pair(&self) -> Result<(&GValue, &GValue), Error> {
+        match self {
+            GValue::Pair(target, stmt) => Ok((target, stmt)),
+            _ => Err(self.type_error("definition")),
+        }
+    }
+    /// Looks up `key` in a map, or among the leaf definitions of a list of
+    /// block entries.
+    pub fn get<K: Key + ?Sized + fmt::Display>(&self, key: &K) -> Result<&GValue, Error> {
+        let found = match self {
+            GValue::Map(map) => map.get(key),
+            GValue::Vec(values) => values
+                .iter()
+                .filter_map(leaf_def)
+                .find(|(k, _)| key.matches_key(k))
+                .map(|(_, v)| v),
+            _ => return Err(self.type_error("map or list")),
+        };
+        found.ok_or_else(|| Error::MissingKey {
+            key: key.to_string(),
+        })
+    }
+    /// Returns the `i`th element of a list or argument of a message.
+    pub fn index(&self, i: usize) -> Result<&GValue, Error> {
+        let values = match self {
+            GValue::Vec(values) | GValue::Message(_, values) => values,
+            _ => return Err(self.type_error("list or message")),
+        };
+        values.get(i).ok_or(Error::IndexOutOfRange {
+            index: i,
+            len: values.len(),
+        })
+    }
+    pub fn edges(&self) -> impl Iterator<Item = EdgeRef<'_>> {
+        self.as_vec()
+            .unwrap_or(&[])
+            .iter()
+            .filter_map(EdgeRef::from_entry)
+    }
+    pub fn leaf_defs(&self) -> impl Iterator<Item = (&GValue, &GValue)> {
+        self.as_vec().unwrap_or(&[]).iter().filter_map(leaf_def)
+    }
+}
+
+impl TryFrom<&GValue> for i64 {
+    type Error = Error;
+
+    fn try_from(value: &GValue) -> Result<Self, Error> {
+        value.as_i64()
+    }
+}
+
+impl TryFrom<&GValue> for f64 {
+    type Error = Error;
+
+    fn try_from(value: &GValue) -> Result<Self, Error> {
+        value.as_f64()
+    }
+}
+
+impl TryFrom<&GValue> for bool {
+    type Error = Error;
+
+    fn try_from(value: &GValue) -> Result<Self, Error> {
+        value.as_bool()
+    }
+}
+
+impl<'a> TryFrom<&'a GValue> for &'a str {
+    type Error = Error;
+
+    fn try_from(value: &'a GValue) -> Result<Self, Error> {
+        value.as_str()
+    }
+}
+
+impl TryFrom<&GValue> for String {
+    type Error = Error;
+
+    fn try_from(value: &GValue) -> Result<Self, Error> {
+        value.as_str().map(str::to_string)
+    }
+}
+
+/// An insertion-ordered map from keys to values, as written in `{...}`.
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct Map {
+    entries: Vec<(GValue, GValue)>,
+}
+
+/// Types that can be used to look up entries of a `Map`.
+///
+/// A `str` matches both symbol and string keys, so `map.get("key")` finds
+/// either `key: ...` or `"key": ...`. A dotted `str` such as `"Router.eth0"`
+/// also matches the path key `Router.eth0`. A `GValue` key matches in the
+/// same way, so the symbol `key` and the string `"key"` are one key.
+pub trait Key {
+    fn matches_key(&self, key: &GValue) -> bool;
+}
+
+impl Key for str {
+    fn matches_key(&self, key: &GValue) -> bool {
+        match key {
+            GValue::Symbol(name) | GValue::String(name) => name == self,
+            GValue::Path(names) => self.split('.').eq(names.iter().map(String::as_str)),
+            _ => false,
+        }
+    }
+}
+
+impl Key for String {
+    fn matches_key(&self, key: &GValue) -> bool {
+        self.as_str().matches_key(key)
+    }
+}
+
+impl Key for i64 {
+    fn matches_key(&self, key: &GValue) -> bool {
+        key == &GValue::Integer(*self)
+    }
+}
+
+impl Key for f64 {
+    fn matches_key(&self, key: &GValue) -> bool {
+        key == &GValue::Float(*self)
+    }
+}
+
+impl Key for GValue {
+    fn matches_key(&self, key: &GValue) -> bool {
+        match (self, key) {
+            (GValue::Symbol(name) | GValue::String(name), key)
+            | (key, GValue::Symbol(name) | GValue::String(name)) => name.matches_key(key),
+            _ => self == key,
+        }
+    }
+}
+
+impl<K: Key + ?Sized> Key for &K {
+    fn matches_key(&self, key: &GValue) -> bool {
+        (**self).matches_key(key)
+    }
+}
+
+impl Map {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+    pub fn get<K: Key + ?Sized>(&self, key: &K) -> Option<&GValue> {
+        self.entries
+            .iter()
+            .find(|(k, _)| key.matches_key(k))
+            .map(|(_, v)| v)
+    }
+    pub fn get_mut<K: Key + ?Sized>(&mut self, key: &K) -> Option<&mut GValue> {
+        self.entries
+            .iter_mut()
+            .find(|(k, _)| key.matches_key(k))
+            .map(|(_, v)| v)
+    }
+    pub fn contains_key<K: Key + ?Sized>(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+    /// Inserts an entry, keeping the position of an existing matching key
+    /// and returning its previous value.
+    pub fn insert(&mut self, key: GValue, value: GValue) -> Option<GValue> {
+        match self.entries.iter_mut().find(|(k, _)| key.matches_key(k)) {
+            Some((_, v)) => Some(std::mem::replace(v, value)),
+            None => {
+                self.entries.push((key, value));
+                None
+            }
+        }
+    }
+    pub fn remove<K: Key + ?Sized>(&mut self, key: &K) -> Option<GValue> {
+        let index = self.entries.iter().position(|(k, _)| key.matches_key(k))?;
+        Some(self.entries.remove(index).1)
+    }
+    pub fn iter(&self) -> slice::Iter<'_, (GValue, GValue)> {
+        self.entries.iter()
+    }
+    pub fn keys(&self) -> impl Iterator<Item = &GValue> {
+        self.entries.iter().map(|(k, _)| k)
+    }
+    pub fn values(&self) -> impl Iterator<Item = &GValue> {
+        self.entries.iter().map(|(_, v)| v)
+    }
+}
+
+impl FromIterator<(GValue, GValue)> for Map {
+    fn from_iter<I: IntoIterator<Item = (GValue, GValue)>>(iter: I) -> Self {
+        let mut map = Map::new();
+        for (key, value) in iter {
+            map.insert(key, value);
+        }
+        map
+    }
+}
+
+impl IntoIterator for Map {
+    type Item = (GValue, GValue);
+    type IntoIter = vec::IntoIter<(GValue, GValue)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Map {
+    type Item = &'a (GValue, GValue);
+    type IntoIter = slice::Iter<'a, (GValue, GValue)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.iter()
+    }
+}
+
+pub(crate) fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic())
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+        && !matches!(name, "true" | "false" | "none")
+}
+
+fn write_list(f: &mut fmt::Formatter, values: &[GValue]) -> fmt::Result {
+    for (i, value) in values.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{}", value)?;
+    }
+    Ok(())
+}
+
+/// Writes the nodes of one side of a hyperedge, `X + Y`.
+fn write_joined(f: &mut fmt::Formatter, values: &[GValue]) -> fmt::Result {
+    for (i, value) in values.iter().enumerate() {
+        if i > 0 {
+            write!(f, " + ")?;
+        }
+        write!(f, "{}", value)?;
+    }
+    Ok(())
+}
+
+/// Formats values in GMML syntax.
+impl fmt::Display for GValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GValue::String(string) => write!(f, "{:?}", string),
+            GValue::Integer(number) => write!(f, "{}", number),
+            GValue::Float(number) => write!(f, "{:?}", number),
+            GValue::Bool(value) => write!(f, "{}", value),
+            GValue::Null => write!(f, "none"),
+            GValue::Symbol(name) if is_identifier(name) => write!(f, "{}", name),
+            GValue::Symbol(name) => write!(f, "`{}`", name),
+            GValue::Path(names) => {
+                for (i, name) in names.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ".")?;
+                    }
+                    if is_identifier(name) {
+                        write!(f, "{}", name)?;
+                    } else {
+                        write!(f, "`{}`", name)?;
+                    }
+                }
+                Ok(())
+            }
+            GValue::Message(name, args) => {
+                write!(f, "{}(", GValue::Symbol(name.clone()))?;
+                write_list(f, args)?;
+                write!(f, ")")
+            }
+            GValue::Edge(from, to, EdgeKind::Reverse) => write!(f, "{} <- {}", to, from),
+            GValue::Edge(from, to, kind) => write!(f, "{} {} {}", from, kind, to),
+            GValue::HyperEdge {
+                sources,
+                targets,
+                kind: EdgeKind::Reverse,
+            } => {
+                write_joined(f, targets)?;
+                write!(f, " <- ")?;
+                write_joined(f, sources)
+            }
+            GValue::HyperEdge {
+                sources,
+                targets,
+                kind,
+            } => {
+                write_joined(f, sources)?;
+                write!(f, " {} ", kind)?;
+                write_joined(f, targets)
+            }
+            GValue::Named { id, edge } => write!(f, "{}: {}", GValue::Symbol(id.clone()), edge),
+            GValue::Vec(values) => {
+                write!(f, "(")?;
+                write_list(f, values)?;
+                write!(f, ")")
+            }
+            GValue::Map(map) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in map.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: {}", key, value)?;
+                }
+                write!(f, "}}")
+            }
+            GValue::Pair(target, stmt) if target.is_edge() => write!(f, "{} : {}", target, stmt),
+            GValue::Pair(target, stmt) => write!(f, "{} = {}", target, stmt),
+        }
+    }
+}
@@ -0,0 +1,105 @@
+#[cfg(test)]
+mod tests {
+    use gmml::{Error, GValue};
+    use std::convert::TryFrom;
+
+    const SRC: &str = "[Definition]
+Symbol = \"something\"
+\"文字列\" = 42
+Ratio = 0.5
+Enabled = true
+ListSyntax = (a, \"b\", 3, 4.0, (list, in, list))
+StructSyntax = {key: \"value\", 3: 4}
+EdgeCan -> CallMessage : message_2(\"foo\", \"bar\")
+Y -> Z : Attack()
+X -> Z
+";
+
+    #[test]
+    fn block_accessors() {
+        let document = gmml::parse_str(SRC).unwrap();
+        let definition = &document["Definition"];
+        assert_eq!(
+            definition.get("Symbol").unwrap().as_str().unwrap(),
+            "something"
+        );
+        assert_eq!(definition.get("文字列").unwrap().as_i64().unwrap(), 42);
+        assert_eq!(definition.get("Ratio").unwrap().as_f64().unwrap(), 0.5);
+        assert!(definition.get("Enabled").unwrap().as_bool().unwrap());
+        assert_eq!(definition.leaf_defs().count(), 6);
+
+        let edges: Vec<_> = definition.edges().collect();
+        assert_eq!(edges.len(), 3);
+        assert_eq!(edges[0].from.as_str().unwrap(), "EdgeCan");
+        assert_eq!(edges[0].message(), Some("message_2"));
+        assert_eq!(edges[1].message(), Some("Attack"));
+        assert_eq!(edges[2].stmt, None);
+
+        let (name, args) = edges[0].stmt.unwrap().as_message().unwrap();
+        assert_eq!(name, "message_2");
+        assert_eq!(args[1].as_str().unwrap(), "bar");
+    }
+
+    #[test]
+    fn value_navigation() {
+        let document = gmml::parse_str(SRC).unwrap();
+        let definition = document["Definition"].to_value();
+        let list = definition.get("ListSyntax").unwrap();
+        assert_eq!(
+            list.index(4)
+                .unwrap()
+                .index(1)
+                .unwrap()
+                .as_symbol()
+                .unwrap(),
+            "in"
+        );
+        assert_eq!(list.index(3).unwrap().as_f64().unwrap(), 4.0);
+        assert_eq!(list.index(2).unwrap().as_f64().unwrap(), 3.0);
+        let map = definition.get("StructSyntax").unwrap();
+        assert_eq!(map.get("key").unwrap().as_str().unwrap(), "value");
+        assert_eq!(map.get(&3).unwrap().as_i64().unwrap(), 4);
+        assert_eq!(map.as_map().unwrap().len(), 2);
+        assert_eq!(definition.edges().count(), 3);
+        assert_eq!(definition.leaf_defs().count(), 6);
+
+        assert_eq!(i64::try_from(map.get(&3).unwrap()).unwrap(), 4);
+        assert_eq!(<&str>::try_from(map.get("key").unwrap()).unwrap(), "value");
+        assert!(bool::try_from(&GValue::Bool(false)).is_ok());
+        assert_eq!(
+            String::try_from(&GValue::Symbol("a".to_string())).unwrap(),
+            "a"
+        );
+    }
+
+    #[test]
+    fn descriptive_errors() {
+        let document = gmml::parse_str(SRC).unwrap();
+        let definition = &document["Definition"];
+        let err = definition.get("Symbol").unwrap().as_i64().unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Type {
+                expected: "integer",
+                found: "string"
+            }
+        ));
+        assert_eq!(err.to_string(), "expected integer, found string");
+        assert_eq!(
+            definition.get("Missing").unwrap_err().to_string(),
+            "no entry for key `Missing`"
+        );
+        let list = definition.get("ListSyntax").unwrap();
+        assert_eq!(
+            list.index(9).unwrap_err().to_string(),
+            "index 9 is out of range for length 5"
+        );
+        assert!(matches!(
+            f64::try_from(list),
+            Err(Error::Type {
+                expected: "number",
+                found: "list"
+            })
+        ));
+    }
+}
@@ -0,0 +1,93 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::{message, symbol};
+    use gmml::{EdgeKind, Error, GValue};
+
+    fn edge(from: &str, to: &str) -> GValue {
+        GValue::Edge(
+            Box::new(symbol(from)),
+            Box::new(symbol(to)),
+            EdgeKind::Directed,
+        )
+    }
+
+    #[test]
+    fn chain_desugars_into_edges() {
+        let src = "[Model]\nA -> B -> C -> D\nD\n  -> E\n  -> F\n";
+        let document = gmml::parse_str(src).unwrap();
+        let block = document.get("Model").unwrap();
+        assert_eq!(
+            block.values().cloned().collect::<Vec<_>>(),
+            vec![
+                edge("A", "B"),
+                edge("B", "C"),
+                edge("C", "D"),
+                edge("D", "E"),
+                edge("E", "F"),
+            ]
+        );
+        let spans: Vec<&str> = block
+            .entries
+            .iter()
+            .map(|entry| &src[entry.span.start..entry.span.end])
+            .collect();
+        assert_eq!(
+            spans,
+            vec!["A -> B", "B -> C", "C -> D", "D\n  -> E", "E\n  -> F"]
+        );
+    }
+
+    #[test]
+    fn chain_statement_applies_to_each_hop() {
+        let src = "[Model]\nX -> Y -> Z : Attack()\n";
+        let document = gmml::parse_str(src).unwrap();
+        let block = document.get("Model").unwrap();
+        let stmt = message("Attack");
+        let edges: Vec<(GValue, GValue, Option<GValue>)> = block
+            .edges()
+            .map(|edge| (edge.from.clone(), edge.to.clone(), edge.stmt.cloned()))
+            .collect();
+        assert_eq!(
+            edges,
+            vec![
+                (symbol("X"), symbol("Y"), Some(stmt.clone())),
+                (symbol("Y"), symbol("Z"), Some(stmt)),
+            ]
+        );
+        let spans: Vec<&str> = block
+            .entries
+            .iter()
+            .map(|entry| &src[entry.span.start..entry.span.end])
+            .collect();
+        assert_eq!(spans, vec!["X -> Y", "Y -> Z"]);
+
+        let err = gmml::parse_str("[Model]\nX -> Y -> Z = 1\n").unwrap_err();
+        assert_eq!(err.location().unwrap().span.line, 2);
+    }
+
+    #[test]
+    fn continuation_lines() {
+        for src in &[
+            "[Model]\nA ; first\n  -> B ; second\n  -> C\n",
+            "[Model]\nA -> B ->\nC\n",
+            "[Model]\nA -> B\n\n  -> C\n",
+        ] {
+            let document = gmml::parse_str(src).unwrap();
+            assert_eq!(
+                document["Model"].values().cloned().collect::<Vec<_>>(),
+                vec![edge("A", "B"), edge("B", "C")],
+                "{}",
+                src
+            );
+        }
+
+        let err = gmml::parse_str("[Model]\nA -> B ->").unwrap_err();
+        assert!(matches!(err, Error::UnexpectedEof { .. }));
+        let span = err.location().unwrap().span;
+        assert_eq!((span.line, span.column), (2, 10));
+        let err = gmml::parse_str("[Model]\nA -> B : Attack()\n-> C\n").unwrap_err();
+        assert_eq!(err.location().unwrap().span.line, 3);
+    }
+}
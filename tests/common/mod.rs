@@ -0,0 +1,15 @@
+#![allow(dead_code)]
+
+use gmml::GValue;
+
+pub fn symbol(name: &str) -> GValue {
+    GValue::Symbol(name.to_string())
+}
+
+pub fn symbols(names: &[&str]) -> Vec<GValue> {
+    names.iter().map(|name| symbol(name)).collect()
+}
+
+pub fn message(name: &str) -> GValue {
+    GValue::Message(name.to_string(), vec![])
+}
@@ -0,0 +1,153 @@
+#[cfg(test)]
+mod tests {
+    use gmml::cst::{self, SyntaxKind};
+    use std::fs;
+
+    #[test]
+    fn round_trip_examples() {
+        for path in &[
+            "example/group_attack.gmml",
+            "example/group_edges.gmml",
+            "example/test1.gmml",
+            "example/test2.gmml",
+        ] {
+            let src = fs::read_to_string(path).unwrap();
+            let document = cst::parse(&src);
+            assert_eq!(document.to_string(), src);
+            assert!(!document.has_errors(), "{}", path);
+            assert_eq!(document.syntax().text_range(), 0..src.len());
+        }
+    }
+
+    #[test]
+    fn round_trip_malformed() {
+        for src in &[
+            "[A]\r\nx = (1, 2\r\n[B] ; comment\r\ny -> \"unterminated\n",
+            "garbage ] } ) \u{a0}\n[A\nx = = 3 @ 4\n{",
+            "[A]\nx = {a: 1, b}\n`open\ny -> 0x_",
+            "",
+        ] {
+            let document = cst::parse(src);
+            assert_eq!(document.to_string(), *src);
+            let tokens = document.syntax().descendant_tokens();
+            let text: String = tokens.iter().map(|token| token.text()).collect();
+            assert_eq!(text, *src);
+        }
+        assert!(cst::parse("[A]\nx = = 3\n").has_errors());
+    }
+
+    #[test]
+    fn typed_accessors() {
+        let src = "; model
+[Exists]
+X ; bare
+\"文字列\" = {has_class: Enemy, hp: 3}
+
+[Model]
+Y -> Z : Attack(1, (2, 3))
+";
+        let document = cst::parse(src);
+        let names: Vec<_> = document.blocks().map(|block| block.name()).collect();
+        assert_eq!(names, vec![Some("Exists".into()), Some("Model".into())]);
+
+        let exists = document.block("Exists").unwrap();
+        let entries: Vec<_> = exists.entries().collect();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].key().unwrap().text(), "X");
+        assert!(entries[0].value().is_none());
+        assert_eq!(entries[1].key().unwrap().text(), "\"文字列\"");
+        assert_eq!(entries[1].operator().unwrap().text(), "=");
+        let value = entries[1].value().unwrap();
+        assert_eq!(value.kind(), SyntaxKind::Struct);
+        let pairs = value.pairs();
+        assert_eq!(pairs[1].0.text(), "hp");
+        assert_eq!(pairs[1].1.as_ref().unwrap().syntax().text(), "3");
+
+        let model = document.block("Model").unwrap();
+        let entry = model.entries().next().unwrap();
+        assert!(entry.key().is_none());
+        let edge = entry.edge().unwrap();
+        assert_eq!(edge.from().unwrap().text(), "Y");
+        assert_eq!(edge.to().unwrap().text(), "Z");
+        let message = entry.value().unwrap();
+        assert_eq!(message.message_name().as_deref(), Some("Attack"));
+        assert_eq!(message.items().len(), 2);
+        assert_eq!(message.items()[1].items()[1].syntax().text(), "3");
+
+        let range = entry.syntax().text_range();
+        assert_eq!(&src[range], "Y -> Z : Attack(1, (2, 3))");
+        let comment = entries[0]
+            .syntax()
+            .tokens()
+            .find(|token| token.kind() == SyntaxKind::Comment)
+            .unwrap();
+        assert_eq!(comment.text(), "; bare");
+        assert_eq!(comment.parent().kind(), SyntaxKind::Entry);
+        assert_eq!(comment.text_range(), 19..25);
+    }
+
+    #[test]
+    fn edge_forms() {
+        let document = cst::parse(
+            "[Model]\nX -> Y -> Z : Attack()\nX -> Y\n(X, Y) -> Z\nX + Y + W -> Z\ne1: X -> Z\n",
+        );
+        let model = document.block("Model").unwrap();
+        let edges: Vec<_> = model.entries().filter_map(|entry| entry.edge()).collect();
+        let keys: Vec<String> = edges[0].keys().map(|key| key.text()).collect();
+        assert_eq!(keys, vec!["X", "Y", "Z"]);
+        assert!(edges[0].is_chain());
+        assert!(!edges[1].is_chain());
+
+        assert!(edges[2].is_grouped());
+        assert!(!edges[1].is_grouped());
+        assert_eq!(edges[2].from(), None);
+        let keys: Vec<String> = edges[2].keys().map(|key| key.text()).collect();
+        assert_eq!(keys, vec!["X", "Y", "Z"]);
+
+        assert!(edges[3].is_hyperedge());
+        assert!(!edges[1].is_hyperedge());
+        assert_eq!(edges[3].from(), None);
+        let keys: Vec<String> = edges[3].keys().map(|key| key.text()).collect();
+        assert_eq!(keys, vec!["X", "Y", "W", "Z"]);
+
+        let ids: Vec<Option<String>> = model
+            .entries()
+            .map(|entry| entry.edge_id().map(|id| id.text()))
+            .collect();
+        assert_eq!(ids, vec![None, None, None, None, Some("e1".to_string())]);
+        assert_eq!(edges[4].from().unwrap().text(), "X");
+    }
+
+    #[test]
+    fn paths() {
+        let document = cst::parse("[Model]\nRouter.eth0 -> `Switch 1`.p1\nX = {a.b: Z.hp}\n");
+        let block = document.block("Model").unwrap();
+        let entries: Vec<_> = block.entries().collect();
+        let edge = entries[0].edge().unwrap();
+        assert_eq!(edge.from().unwrap().text(), "Router.eth0");
+        assert_eq!(edge.to().unwrap().text(), "`Switch 1`.p1");
+        let pairs = entries[1].value().unwrap().pairs();
+        assert_eq!(pairs[0].0.text(), "a.b");
+        assert_eq!(pairs[0].1.as_ref().unwrap().syntax().text(), "Z.hp");
+    }
+
+    #[test]
+    fn half_typed_edge_id() {
+        for src in &[
+            "[M]\nB:",
+            "[M]\nB:\n[N]\n",
+            "[M]\nB: ; id\n",
+            "[M]\nB:\nX -> Y\n",
+        ] {
+            let document = cst::parse(src);
+            assert_eq!(document.to_string(), *src);
+            assert!(document.has_errors(), "{}", src);
+            let entry = document.block("M").unwrap().entries().next().unwrap();
+            assert_eq!(entry.edge_id().unwrap().text(), "B");
+            assert!(entry.edge().is_none());
+        }
+        let document = cst::parse("[M]\nB:\n[N]\n");
+        let names: Vec<_> = document.blocks().map(|block| block.name()).collect();
+        assert_eq!(names, vec![Some("M".into()), Some("N".into())]);
+    }
+}
@@ -0,0 +1,141 @@
+#[cfg(test)]
+mod tests {
+    use gmml::Error;
+    use serde::Deserialize;
+    use std::collections::{BTreeMap, HashMap};
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    enum Class {
+        Enemy,
+        Ally,
+    }
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Unit {
+        has_class: Class,
+        hp: Option<u32>,
+    }
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    enum Action {
+        Attack(),
+        Heal(u32),
+        Move(i64, i64),
+        Say { text: String },
+    }
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Model {
+        #[serde(rename = "Exists")]
+        exists: BTreeMap<String, Option<Unit>>,
+        #[serde(rename = "Env")]
+        env: HashMap<(String, String), String>,
+        #[serde(rename = "Model")]
+        model: Vec<((String, String), Action)>,
+    }
+
+    const SRC: &str = "[Exists]
+X
+Z = {has_class: Enemy, hp: 3}
+
+[Env]
+X -> Y : Positive
+Y -> Z : Negative
+
+[Model]
+Y -> Z : Attack()
+Z -> Y : Heal(2)
+X -> Z : Move(1, -1)
+X -> Y : Say({text: \"hi\"})
+";
+
+    #[test]
+    fn deserialize_model() {
+        let model: Model = gmml::from_str(SRC).unwrap();
+        assert_eq!(model.exists.len(), 2);
+        assert_eq!(model.exists["X"], None);
+        assert_eq!(
+            model.exists["Z"],
+            Some(Unit {
+                has_class: Class::Enemy,
+                hp: Some(3)
+            })
+        );
+        assert_eq!(model.env[&("X".to_string(), "Y".to_string())], "Positive");
+        let actions: Vec<&Action> = model.model.iter().map(|(_, action)| action).collect();
+        assert_eq!(
+            actions,
+            vec![
+                &Action::Attack(),
+                &Action::Heal(2),
+                &Action::Move(1, -1),
+                &Action::Say {
+                    text: "hi".to_string()
+                }
+            ]
+        );
+        assert_eq!(model.model[0].0, ("Y".to_string(), "Z".to_string()));
+    }
+
+    #[test]
+    fn deserialize_definitions() {
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Definition {
+            #[serde(rename = "Symbol")]
+            symbol: String,
+            #[serde(rename = "ListSyntax")]
+            list: (String, String, u8, f32, Vec<String>),
+            #[serde(rename = "Enabled")]
+            enabled: bool,
+            #[serde(rename = "Missing", default)]
+            missing: Option<i64>,
+        }
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Document {
+            #[serde(rename = "Definition")]
+            definition: Definition,
+        }
+        let src = "[Definition]
+Symbol = \"something\"
+ListSyntax = (a, \"b\", 3, 4.0, (list, in, list))
+Enabled = true
+";
+        let document: Document = gmml::from_str(src).unwrap();
+        assert_eq!(document.definition.symbol, "something");
+        assert_eq!(document.definition.list.3, 4.0);
+        assert_eq!(document.definition.list.4, vec!["list", "in", "list"]);
+        assert!(document.definition.enabled);
+        assert_eq!(document.definition.missing, None);
+
+        let value = gmml::GValue::Vec(vec![gmml::GValue::Integer(1)]);
+        assert_eq!(gmml::from_value::<Vec<i64>>(&value).unwrap(), vec![1]);
+    }
+
+    #[test]
+    fn errors_point_at_entry() {
+        let src = "[Exists]\nX\nZ = {has_class: Neutral}\n";
+        let err = gmml::from_str::<Model>(src).unwrap_err();
+        match &err {
+            Error::Deserialize {
+                location: Some(location),
+                message,
+            } => {
+                assert_eq!((location.span.line, location.span.column), (3, 1));
+                assert!(message.contains("Neutral"), "{}", message);
+            }
+            other => panic!("{:?}", other),
+        }
+        assert!(err
+            .to_string()
+            .starts_with("3:1: unknown variant `Neutral`"));
+
+        let err = gmml::from_str::<Model>("[Exists]\n").unwrap_err();
+        assert_eq!(err.to_string(), "missing field `Env`");
+
+        let err = gmml::from_str::<HashMap<String, HashMap<String, u8>>>("[A]\nx = 300\n");
+        assert_eq!(
+            err.unwrap_err().to_string(),
+            "2:1: invalid value: integer `300`, expected u8"
+        );
+    }
+}
@@ -0,0 +1,56 @@
+#[cfg(test)]
+mod tests {
+    use gmml::diagnostic::Diagnostic;
+    use gmml::Span;
+
+    #[test]
+    fn render_missing_parentheses() {
+        let src = "[Model]\nY -> Z : Attack x\n";
+        let err = gmml::parse_str(src).unwrap_err();
+        let rendered = Diagnostic::from(&err).render(src, false);
+        assert_eq!(
+            rendered,
+            "error: arguments to message `Attack` must be enclosed in parentheses
+ --> <input>:2:17
+  |
+2 | Y -> Z : Attack x
+  |          ------ message called here
+  |                 ^ expected `(`
+  |
+  = help: parentheses can not be omitted: write `Attack(...)`
+"
+        );
+    }
+
+    #[test]
+    fn render_multiline_with_note() {
+        let src = "[A]\nX = {a: 1,\n     b: 2}\n";
+        let rendered = Diagnostic::warning("suspicious struct")
+            .with_file("model.gmml")
+            .with_label(Span::new(8, 28, 2, 5), "this struct")
+            .with_note("structs may span lines")
+            .render(src, false);
+        assert_eq!(
+            rendered,
+            "warning: suspicious struct
+ --> model.gmml:2:5
+  |
+2 | X = {a: 1,
+  |     ^^^^^^
+3 |      b: 2}
+  | ^^^^^^^^^^ this struct
+  |
+  = note: structs may span lines
+"
+        );
+    }
+
+    #[test]
+    fn render_with_color() {
+        let src = "[A]\nX = $\n";
+        let err = gmml::parse_str(src).unwrap_err();
+        let rendered = Diagnostic::from(&err).render(src, true);
+        assert!(rendered.starts_with("\x1b[1;31merror\x1b[0m"));
+        assert!(rendered.contains("\x1b[1;31m^ unexpected character\x1b[0m"));
+    }
+}
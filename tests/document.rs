@@ -0,0 +1,62 @@
+#[cfg(test)]
+mod tests {
+    use gmml::parse::{Parser, Scanner};
+    use gmml::{Document, DuplicateBlocks, Error, GValue};
+
+    const SRC: &str = "[Model]\nY -> Z : Attack()\n[Exists]\nZ\n[Model]\nX -> Z : Attack()\n";
+
+    fn parse(policy: DuplicateBlocks) -> Result<Document, Error> {
+        let toks = Scanner::from_str(SRC).tokenize()?;
+        Parser::new(toks).duplicate_blocks(policy).parse()
+    }
+
+    #[test]
+    fn blocks_keep_source_order() {
+        let document = gmml::parse_str("[C]\n[A]\nX\n[B]\n").unwrap();
+        assert_eq!(document.names().collect::<Vec<_>>(), vec!["C", "A", "B"]);
+        assert_eq!(
+            document["A"].values().collect::<Vec<_>>(),
+            vec![&GValue::Symbol("X".to_string())]
+        );
+        assert!(document.get("D").is_none());
+    }
+
+    #[test]
+    fn duplicate_blocks_are_errors_by_default() {
+        match gmml::parse_str(SRC).unwrap_err() {
+            Error::DuplicateBlock {
+                name,
+                first,
+                location,
+            } => {
+                assert_eq!(name, "Model");
+                assert_eq!((first.line, first.column), (1, 2));
+                assert_eq!((location.span.line, location.span.column), (5, 2));
+            }
+            err => panic!("unexpected error: {:?}", err),
+        }
+        assert!(parse(DuplicateBlocks::Error).is_err());
+    }
+
+    #[test]
+    fn merge_duplicate_blocks() {
+        let document = parse(DuplicateBlocks::Merge).unwrap();
+        assert_eq!(
+            document.names().collect::<Vec<_>>(),
+            vec!["Model", "Exists"]
+        );
+        assert_eq!(document["Model"].len(), 2);
+        assert_eq!(document["Model"].entries[1].span.line, 6);
+    }
+
+    #[test]
+    fn keep_all_duplicate_blocks() {
+        let document = parse(DuplicateBlocks::KeepAll).unwrap();
+        assert_eq!(
+            document.names().collect::<Vec<_>>(),
+            vec!["Model", "Exists", "Model"]
+        );
+        assert_eq!(document.get_all("Model").count(), 2);
+        assert_eq!(document["Model"].entries[0].span.line, 2);
+    }
+}
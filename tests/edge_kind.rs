@@ -0,0 +1,141 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::symbol;
+    use gmml::format::format_str;
+    use gmml::parse::{Scanner, Symbol, Token};
+    use gmml::{EdgeKind, GValue};
+
+    const SRC: &str = "[Model]
+A -> B
+A <- B : Influence()
+A <-> B
+A -- B -> C
+A < B
+";
+
+    #[test]
+    fn edge_operators() {
+        let toks: Vec<Token> = Scanner::from_str("-> <- <-> -- - < -1")
+            .tokenize()
+            .unwrap()
+            .into_iter()
+            .map(|tok| tok.node)
+            .filter(|tok| *tok != Token::Whitespace)
+            .collect();
+        assert_eq!(
+            toks,
+            vec![
+                Token::Arrow(EdgeKind::Directed),
+                Token::Arrow(EdgeKind::Reverse),
+                Token::Arrow(EdgeKind::Bidirectional),
+                Token::Arrow(EdgeKind::Undirected),
+                Token::Symbol(Symbol::Minus),
+                Token::Symbol(Symbol::Bt),
+                Token::Integer(-1),
+            ]
+        );
+
+        let src = SRC.replace("A < B\n", "");
+        let document = gmml::parse_str(&src).unwrap();
+        let block = document.get("Model").unwrap();
+        let edges: Vec<(String, String, EdgeKind)> = block
+            .edges()
+            .map(|edge| (edge.from.to_string(), edge.to.to_string(), edge.kind))
+            .collect();
+        let edge = |from: &str, to: &str, kind| (from.to_string(), to.to_string(), kind);
+        assert_eq!(
+            edges,
+            vec![
+                edge("A", "B", EdgeKind::Directed),
+                edge("B", "A", EdgeKind::Reverse),
+                edge("A", "B", EdgeKind::Bidirectional),
+                edge("A", "B", EdgeKind::Undirected),
+                edge("B", "C", EdgeKind::Directed),
+            ]
+        );
+        let written: Vec<String> = block.values().map(|value| value.to_string()).collect();
+        assert_eq!(
+            written,
+            vec![
+                "A -> B",
+                "A <- B : Influence()",
+                "A <-> B",
+                "A -- B",
+                "B -> C"
+            ]
+        );
+
+        let err = gmml::parse_str(SRC).unwrap_err();
+        assert_eq!(err.location().unwrap().span.line, 6);
+    }
+
+    #[test]
+    fn query_and_serde_keep_kind() {
+        let src = SRC.replace("A < B\n", "");
+        let document = gmml::parse_str(&src).unwrap();
+        let matches = gmml::query(&document, "Model/edges[kind=undirected]").unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].to_string(), "A -- B");
+        let matches = gmml::query(&document, "Model/edges[kind!=directed][from=B]").unwrap();
+        assert_eq!(matches.len(), 1);
+
+        let value = GValue::Edge(
+            Box::new(symbol("A")),
+            Box::new(symbol("B")),
+            EdgeKind::Bidirectional,
+        );
+        assert_eq!(gmml::to_value(&value).unwrap(), value);
+        let copy = gmml::to_document(&document).unwrap();
+        assert_eq!(
+            gmml::ser::write_document(&copy).unwrap(),
+            src.replace("A -- B -> C", "A -- B\nB -> C")
+        );
+    }
+
+    #[test]
+    fn reverse_edges_keep_direction() {
+        let document = gmml::parse_str("[Model]\nC <- D\n").unwrap();
+        let entry = &document["Model"].entries[0].node;
+        assert_eq!(
+            entry,
+            &GValue::Edge(
+                Box::new(symbol("D")),
+                Box::new(symbol("C")),
+                EdgeKind::Reverse
+            )
+        );
+        assert_eq!(entry.to_string(), "C <- D");
+        assert_eq!(
+            gmml::from_value::<(String, String)>(entry).unwrap(),
+            ("D".to_string(), "C".to_string())
+        );
+        assert_eq!(gmml::to_value(entry).unwrap(), *entry);
+    }
+
+    #[test]
+    fn operators_without_spaces() {
+        for (src, written) in &[
+            ("A->-1", "A -> -1"),
+            ("-1->A", "-1 -> A"),
+            ("A<-B", "A <- B"),
+            ("A<->B", "A <-> B"),
+            ("A -- -1", "A -- -1"),
+            ("A--1", "A -- 1"),
+        ] {
+            let src = format!("[Model]\n{}\n", src);
+            let document = gmml::parse_str(&src).unwrap();
+            assert_eq!(
+                document["Model"].values().next().unwrap().to_string(),
+                *written
+            );
+            assert_eq!(format_str(&src).unwrap(), format!("[Model]\n{}\n", written));
+        }
+        for src in &["A<--B", "A<-->B", "A->->B", "A - B"] {
+            let src = format!("[Model]\n{}\n", src);
+            assert!(gmml::parse_str(&src).is_err(), "{}", src);
+            assert!(format_str(&src).is_err(), "{}", src);
+        }
+    }
+}
@@ -0,0 +1,244 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::{message, symbol};
+    use gmml::{DocumentMut, EdgeKind, Error, GValue};
+
+    const SRC: &str = "; Group attack model
+
+[Exists]
+  X
+  Y   ; the target
+  Z = {
+    has_class: Enemy
+    }
+
+[Env]
+Hello = 123.456 ; keep me
+X -> Y : Positive
+
+[Model]
+Y -> Z : Attack() ; parentheses can not be omitted
+";
+
+    #[test]
+    fn edit_entries() {
+        let mut document: DocumentMut = SRC.parse().unwrap();
+        document
+            .set("Env", symbol("Hello"), GValue::Float(42.5))
+            .unwrap();
+        document
+            .push_edge("Model", symbol("Z"), symbol("Y"), Some(message("Attack")))
+            .unwrap();
+        document.push("Exists", symbol("W")).unwrap();
+        document.remove("Exists", "Y").unwrap();
+        document
+            .set_edge("Env", "X", "Y", symbol("Negative"))
+            .unwrap();
+        document
+            .set(
+                "Env",
+                GValue::String("文字列".to_string()),
+                GValue::Integer(1),
+            )
+            .unwrap();
+        assert_eq!(
+            document.to_string(),
+            "; Group attack model
+
+[Exists]
+  X
+  Z = {
+    has_class: Enemy
+    }
+  W
+
+[Env]
+Hello = 42.5 ; keep me
+X -> Y : Negative
+\"文字列\" = 1
+
+[Model]
+Y -> Z : Attack() ; parentheses can not be omitted
+Z -> Y : Attack()
+"
+        );
+        let parsed = document.to_document().unwrap();
+        assert_eq!(parsed["Model"].len(), 2);
+    }
+
+    #[test]
+    fn edit_blocks() {
+        let mut document = DocumentMut::parse(SRC).unwrap();
+        document.rename_block("Env", "Environment").unwrap();
+        document.remove_block("Exists").unwrap();
+        document.push_block("Empty").unwrap();
+        document.push("Empty", symbol("A")).unwrap();
+        document.remove_edge("Model", "Y", "Z").unwrap();
+        assert_eq!(
+            document.as_str(),
+            "; Group attack model
+
+[Environment]
+Hello = 123.456 ; keep me
+X -> Y : Positive
+
+[Model]
+
+[Empty]
+A
+"
+        );
+    }
+
+    #[test]
+    fn rejected_edits() {
+        let mut document = DocumentMut::parse("[A]\nx = 1\n\n[B]").unwrap();
+        assert!(matches!(
+            document.rename_block("A", "B"),
+            Err(Error::DuplicateBlock { .. })
+        ));
+        assert!(matches!(
+            document.remove("A", "y"),
+            Err(Error::MissingKey { .. })
+        ));
+        assert!(matches!(
+            document.push("C", symbol("x")),
+            Err(Error::MissingBlock { .. })
+        ));
+        assert!(document
+            .push("A", GValue::Vec(vec![GValue::Integer(1)]))
+            .is_err());
+        assert_eq!(document.as_str(), "[A]\nx = 1\n\n[B]");
+        document.push("B", symbol("y")).unwrap();
+        document.set("A", symbol("x"), GValue::Null).unwrap();
+        assert_eq!(document.as_str(), "[A]\nx = none\n\n[B]\ny\n");
+        assert!(DocumentMut::parse("[A]\nx = \n").is_err());
+    }
+
+    #[test]
+    fn edit_chains() {
+        let mut document = DocumentMut::parse("[Model]\nX -> Y -> Z : Attack()\nX -> Y\n").unwrap();
+        document.remove_edge("Model", "X", "Y").unwrap();
+        assert_eq!(document.as_str(), "[Model]\nX -> Y -> Z : Attack()\n");
+        assert!(matches!(
+            document.set_edge("Model", "Y", "Z", symbol("Defend")),
+            Err(Error::MissingKey { .. })
+        ));
+    }
+
+    #[test]
+    fn edit_reverse_edges() {
+        let mut document =
+            DocumentMut::parse("[Model]\nA -> B\nA <- B : Influence()\nA <-> B\n").unwrap();
+        document
+            .set_edge("Model", "B", "A", message("Cause"))
+            .unwrap();
+        document.remove_edge("Model", "A", "B").unwrap();
+        assert_eq!(document.as_str(), "[Model]\nA <- B : Cause()\nA <-> B\n");
+    }
+
+    #[test]
+    fn push_groups() {
+        let mut document = DocumentMut::parse("[Model]\n(X, Y) -> Z : Attack()\n").unwrap();
+        document
+            .push(
+                "Model",
+                GValue::Edge(
+                    Box::new(symbol("Z")),
+                    Box::new(GValue::Vec(vec![symbol("X"), symbol("Y")])),
+                    EdgeKind::Directed,
+                ),
+            )
+            .unwrap();
+        assert_eq!(
+            document.as_str(),
+            "[Model]\n(X, Y) -> Z : Attack()\nZ -> (X, Y)\n"
+        );
+        assert_eq!(document.to_document().unwrap()["Model"].len(), 4);
+    }
+
+    #[test]
+    fn edit_by_id() {
+        let mut document = DocumentMut::parse(
+            "[Model]\ne1: Y -> Z : Attack()\ne2: Y -> Z : Defend()\nY -> Z : Retreat()\nj1: X + Y -> Z\n",
+        )
+        .unwrap();
+        document
+            .set_edge_by_id("Model", "e2", message("Counter"))
+            .unwrap();
+        document
+            .set_edge_by_id("Model", "j1", message("JointAttack"))
+            .unwrap();
+        document.remove_edge_by_id("Model", "e1").unwrap();
+        assert_eq!(
+            document.as_str(),
+            "[Model]\ne2: Y -> Z : Counter()\nY -> Z : Retreat()\nj1: X + Y -> Z : JointAttack()\n"
+        );
+        assert!(document.remove_edge_by_id("Model", "e1").is_err());
+    }
+
+    #[test]
+    fn edit_paths() {
+        let mut document = DocumentMut::parse(
+            "[Model]\nRouter.eth0 -> Switch.p1 : Link()\nRouter.eth1 = Z.has_class\nZ -> Y\n",
+        )
+        .unwrap();
+        document
+            .set_edge("Model", "Router.eth0", "Switch.p1", message("Trunk"))
+            .unwrap();
+        document.remove_edge("Model", "Z", "Y").unwrap();
+        document.remove("Model", "Router.eth1").unwrap();
+        document
+            .set(
+                "Model",
+                GValue::Path(vec!["Router".to_string(), "eth2".to_string()]),
+                GValue::Integer(2),
+            )
+            .unwrap();
+        assert_eq!(
+            document.as_str(),
+            "[Model]\nRouter.eth0 -> Switch.p1 : Trunk()\nRouter.eth2 = 2\n"
+        );
+    }
+
+    #[test]
+    fn edit_nested_blocks() {
+        let mut document = DocumentMut::parse(
+            "[Model]\n[Model.Attack]\n[Model.Attack.Phase1]\nY -> Z\n[Model.Defense]\n",
+        )
+        .unwrap();
+        document
+            .rename_block("Model.Attack.Phase1", "Model.Attack.Opening")
+            .unwrap();
+        document.push_block("Model.Defense.Phase1").unwrap();
+        document
+            .push(
+                "Model.Defense.Phase1",
+                GValue::Pair(Box::new(symbol("Cost")), Box::new(GValue::Integer(3))),
+            )
+            .unwrap();
+        assert_eq!(
+            document.as_str(),
+            "[Model]\n[Model.Attack]\n[Model.Attack.Opening]\nY -> Z\n[Model.Defense]\n\n[Model.Defense.Phase1]\nCost = 3\n"
+        );
+        assert!(document.push_block("Model..Defense").is_err());
+    }
+
+    #[test]
+    fn keep_crlf_line_endings() {
+        let mut document = DocumentMut::parse("[A]\r\nx = 1\r\n[B]").unwrap();
+        document.push("A", symbol("y")).unwrap();
+        document.push("B", symbol("z")).unwrap();
+        document.push_block("C").unwrap();
+        document
+            .push_edge("C", symbol("X"), symbol("Y"), None)
+            .unwrap();
+        document.remove("A", "x").unwrap();
+        assert_eq!(
+            document.as_str(),
+            "[A]\r\ny\r\n[B]\r\nz\r\n\r\n[C]\r\nX -> Y\r\n"
+        );
+    }
+}
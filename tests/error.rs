@@ -0,0 +1,80 @@
+#[cfg(test)]
+mod tests {
+    use gmml::Error;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn parse_err(src: &str) -> Error {
+        gmml::parse_str(src).expect_err("parsing should fail")
+    }
+
+    #[test]
+    fn unexpected_token() {
+        let err = parse_err("[A]\nX = 1 2\n");
+        match &err {
+            Error::UnexpectedToken {
+                expected, location, ..
+            } => {
+                assert_eq!(*expected, "newline");
+                assert_eq!(location.span.line, 2);
+                assert_eq!(location.span.column, 7);
+                assert_eq!(location.span.start, 10);
+                assert_eq!(location.span.end, 11);
+            }
+            _ => panic!("unexpected error: {:?}", err),
+        }
+        assert_eq!(err.to_string(), "2:7: expected newline, found number `2`");
+    }
+
+    #[test]
+    fn lexical_errors() {
+        assert!(matches!(
+            parse_err("[A]\nX = \"open\n"),
+            Error::UnterminatedString { .. }
+        ));
+        assert!(matches!(
+            parse_err("[A]\nX = 1.2.3\n"),
+            Error::InvalidNumber { .. }
+        ));
+        assert!(matches!(
+            parse_err("[A]\nX = 1 $\n"),
+            Error::UnexpectedChar { found: '$', .. }
+        ));
+        assert!(matches!(
+            parse_err("[A]\nX = (a, b"),
+            Error::UnexpectedEof { .. }
+        ));
+    }
+
+    #[test]
+    fn eof_after_non_ascii() {
+        let src = "[A]\nX = {a: 文字";
+        let err = parse_err(src);
+        assert!(matches!(err, Error::UnexpectedEof { .. }));
+        let span = err.location().unwrap().span;
+        assert_eq!((span.line, span.column), (2, 11));
+        assert_eq!(span.start, src.len());
+        let (_, errors) = gmml::parse_str_recovering(src);
+        assert_eq!(errors[0].location().unwrap().span.column, 11);
+        let rendered = gmml::diagnostic::Diagnostic::from(&err).render(src, false);
+        assert!(
+            rendered.contains("2 | X = {a: 文字\n  |           ^"),
+            "{}",
+            rendered
+        );
+    }
+
+    #[test]
+    fn truncated_examples_do_not_panic() {
+        let mut example_path = PathBuf::new();
+        example_path.push(env!("CARGO_MANIFEST_DIR"));
+        example_path.push("example");
+        for entry in fs::read_dir(&example_path).unwrap() {
+            let src = fs::read_to_string(entry.unwrap().path()).unwrap();
+            for (i, _) in src.char_indices() {
+                let _ = gmml::parse_str(&src[..i]);
+                let _ = gmml::parse_str_recovering(&src[..i]);
+            }
+        }
+    }
+}
@@ -18,8 +18,8 @@ mod tests {
                     },
                     |path_str| {
                         println!("{}:", path_str);
-                        let scanner = parse::Scanner::new(path_str.to_string());
-                        let sym: Vec<parse::Token> = scanner.into_iter().collect();
+                        let scanner = parse::Scanner::new(path_str.to_string()).unwrap();
+                        let sym = scanner.tokenize().expect("failed to tokenize");
                         let mut parser = parse::Parser::new(sym);
                         let result = parser.parse().expect("failed to parse");
                         println!("{:#?}\n", result);
@@ -28,4 +28,58 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn parse_from_memory() {
+        let src = "[Definition]\nHello = 123.456\nX -> Y : Attack()\n";
+        let from_str = gmml::parse_str(src).expect("failed to parse");
+        let from_reader = gmml::parse_reader(src.as_bytes()).expect("failed to parse");
+        assert_eq!(from_str, from_reader);
+        assert!(from_str.contains("Definition"));
+    }
+
+    fn block_sizes(path: &str) -> Vec<(String, usize)> {
+        let mut example_path = PathBuf::new();
+        example_path.push(env!("CARGO_MANIFEST_DIR"));
+        example_path.push("example");
+        example_path.push(path);
+        let root = gmml::parse_file(example_path.to_str().unwrap()).expect("failed to parse");
+        root.into_iter()
+            .map(|block| (block.name, block.entries.len()))
+            .collect()
+    }
+
+    #[test]
+    fn example_blocks() {
+        let sizes = |blocks: &[(&str, usize)]| -> Vec<(String, usize)> {
+            blocks
+                .iter()
+                .map(|&(name, len)| (name.to_string(), len))
+                .collect()
+        };
+        assert_eq!(
+            block_sizes("group_attack.gmml"),
+            sizes(&[("Exists", 3), ("Env", 2), ("Model", 3)])
+        );
+        assert_eq!(
+            block_sizes("group_edges.gmml"),
+            sizes(&[("Exists", 3), ("Model", 4)])
+        );
+        assert_eq!(
+            block_sizes("test1.gmml"),
+            sizes(&[("A", 0), ("B", 0), ("C", 2), ("D", 3), ("E", 4)])
+        );
+        assert_eq!(
+            block_sizes("test2.gmml"),
+            sizes(&[("BlockName", 5), ("Definition", 8)])
+        );
+    }
+
+    #[test]
+    fn blank_and_comment_lines_in_block() {
+        let src = "[Model]\nY -> Z : Attack()\n\n; defenders\n  ; indented comment\nZ -> Y : Attack()\n\n\nX -> Z : Attack()\n[Env]\n\nX -> Y\n";
+        let root = gmml::parse_str(src).expect("failed to parse");
+        assert_eq!(root["Model"].len(), 3);
+        assert_eq!(root["Env"].len(), 1);
+    }
 }
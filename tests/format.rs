@@ -0,0 +1,143 @@
+#[cfg(test)]
+mod tests {
+    use gmml::format::format_str;
+    use std::fs;
+
+    #[test]
+    fn canonical_spacing() {
+        let src = "[A];Comment
+Symbol=\"something\"
+\"NodeA\"->\"NodeB\":EdgeDefinition
+ListSyntax=(a,\"b\",3,(list,in,list),)
+Fizz = { \"mod\": 3 ,output:\"fizz\" }
+MessageCalling=Message1( something,argument )
+[B]
+";
+        assert_eq!(
+            format_str(src).unwrap(),
+            "[A] ;Comment
+Symbol = \"something\"
+\"NodeA\" -> \"NodeB\" : EdgeDefinition
+ListSyntax = (a, \"b\", 3, (list, in, list))
+Fizz = {\"mod\": 3, output: \"fizz\"}
+MessageCalling = Message1(something, argument)
+
+[B]
+"
+        );
+    }
+
+    #[test]
+    fn edge_spacing() {
+        for (src, formatted) in &[
+            ("X+Y  ->Z", "X + Y -> Z"),
+            ("X +\nY -> Z", "X + Y -> Z"),
+            ("X + Y\n-> Z", "X + Y -> Z"),
+            ("X + Y ; sources\n-> Z", "X + Y ; sources\n-> Z"),
+            ("Router.eth0->Switch.p1", "Router.eth0 -> Switch.p1"),
+            ("X={a.b :Z.hp}", "X = {a.b: Z.hp}"),
+        ] {
+            assert_eq!(
+                format_str(&format!("[Model]\n{}\n", src)).unwrap(),
+                format!("[Model]\n{}\n", formatted)
+            );
+        }
+    }
+
+    #[test]
+    fn nested_block_headers() {
+        assert_eq!(
+            format_str("[Model.Attack];c\nX->Y\n[`Model`.`Attack 1`]\n").unwrap(),
+            "[Model.Attack] ;c\nX -> Y\n\n[`Model`.`Attack 1`]\n"
+        );
+        assert!(format_str("[`Model.Attack`]\n").is_err());
+    }
+
+    #[test]
+    fn multiline_and_comments() {
+        let src = "
+
+; Group attack model
+[Exists]
+X
+
+
+
+Y ; trailing
+StructSyntax = {key: \"value\",
+  \"key 2\":value, ; inside
+     3 :4}
+Z = {
+  has_class: Enemy
+  }
+; attached to the next block
+[Model]
+Y -> Z : Attack()";
+        assert_eq!(
+            format_str(src).unwrap(),
+            "; Group attack model
+[Exists]
+X
+
+Y ; trailing
+StructSyntax = {key: \"value\",
+                \"key 2\": value, ; inside
+                3: 4}
+Z = {has_class: Enemy}
+; attached to the next block
+[Model]
+Y -> Z : Attack()
+"
+        );
+    }
+
+    #[test]
+    fn idempotent_and_equivalent() {
+        for path in &[
+            "example/group_attack.gmml",
+            "example/group_edges.gmml",
+            "example/test1.gmml",
+            "example/test2.gmml",
+        ] {
+            let src = fs::read_to_string(path).unwrap();
+            let formatted = format_str(&src).unwrap();
+            assert_eq!(format_str(&formatted).unwrap(), formatted);
+            assert_eq!(
+                gmml::parse_str(&formatted).unwrap(),
+                gmml::parse_str(&src).unwrap()
+            );
+        }
+        assert!(format_str("[A]\nx = \n").is_err());
+    }
+
+    #[test]
+    fn examples_are_formatted() {
+        for entry in fs::read_dir("example").unwrap() {
+            let path = entry.unwrap().path();
+            let src = fs::read_to_string(&path).unwrap();
+            assert_eq!(format_str(&src).unwrap(), src, "{}", path.display());
+        }
+    }
+
+    #[test]
+    fn comment_before_continuation() {
+        for src in &[
+            "[M]\nA ; note\n-> B\n",
+            "[M]\nA -> B ; note\n  -> C\n",
+            "[M]\nA -> ; note\nB\n",
+        ] {
+            let formatted = format_str(src).unwrap();
+            assert_eq!(
+                gmml::parse_str(&formatted).unwrap(),
+                gmml::parse_str(src).unwrap(),
+                "{}",
+                formatted
+            );
+            assert_eq!(format_str(&formatted).unwrap(), formatted);
+        }
+        assert_eq!(
+            format_str("[M]\nA ; note\n-> B\n").unwrap(),
+            "[M]\nA ; note\n-> B\n"
+        );
+    }
+}
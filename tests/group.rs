@@ -0,0 +1,97 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::symbol;
+    use gmml::parse::{Parser, Scanner};
+    use gmml::{EdgeKind, GValue};
+
+    const SRC: &str = "[Model]
+(X, Y) -> Z : Attack()
+A -> (B, C) -- (D, E)
+";
+
+    fn edges(document: &gmml::Document) -> Vec<String> {
+        document
+            .get("Model")
+            .unwrap()
+            .values()
+            .map(|value| value.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn groups_expand_into_cross_product() {
+        let document = gmml::parse_str(SRC).unwrap();
+        assert_eq!(
+            edges(&document),
+            vec![
+                "X -> Z : Attack()",
+                "Y -> Z : Attack()",
+                "A -> B",
+                "A -> C",
+                "B -- D",
+                "B -- E",
+                "C -- D",
+                "C -- E",
+            ]
+        );
+        let block = document.get("Model").unwrap();
+        let spans: Vec<&str> = block
+            .entries
+            .iter()
+            .map(|entry| &SRC[entry.span.start..entry.span.end])
+            .collect();
+        assert_eq!(spans[..3], ["(X, Y) -> Z", "(X, Y) -> Z", "A -> (B, C)"]);
+
+        for src in &[
+            "[Model]\n(X, Y)\n",
+            "[Model]\n(X, Y) = 1\n",
+            "[Model]\n() -> Z\n",
+        ] {
+            assert!(gmml::parse_str(src).is_err(), "{}", src);
+        }
+    }
+
+    #[test]
+    fn grouped_form() {
+        let toks = Scanner::from_str(SRC).tokenize().unwrap();
+        let document = Parser::new(toks).grouped_edges(true).parse().unwrap();
+        assert_eq!(
+            edges(&document),
+            vec!["(X, Y) -> Z : Attack()", "A -> (B, C)", "(B, C) -- (D, E)"]
+        );
+        let edge = document.get("Model").unwrap().edges().next().unwrap();
+        assert_eq!(edge.from, &GValue::Vec(vec![symbol("X"), symbol("Y")]));
+        assert_eq!(edge.kind, EdgeKind::Directed);
+        assert_eq!(
+            gmml::parse_str(&gmml::to_string(&document).unwrap()).unwrap(),
+            gmml::parse_str(SRC).unwrap()
+        );
+    }
+
+    #[test]
+    fn group_layout() {
+        for src in &[
+            "[Model]\n(X, ; first\n Y,) -> Z\n",
+            "[Model]\n(X,\nY) -> Z\n",
+            "[Model]\n(X, Y,) -> Z\n",
+        ] {
+            let document = gmml::parse_str(src).unwrap();
+            assert_eq!(edges(&document), vec!["X -> Z", "Y -> Z"], "{}", src);
+            let formatted = gmml::format::format_str(src).unwrap();
+            assert_eq!(gmml::parse_str(&formatted).unwrap(), document, "{}", src);
+        }
+
+        for (src, column) in &[
+            ("[Model]\n(A, B) + C -> Z\n", 8),
+            ("[Model]\nA + (B, C) -> Z\n", 5),
+            ("[Model]\n((A, B), C) -> Z\n", 2),
+            ("[Model]\n(X, {a: 1}) -> Z\n", 5),
+            ("[Model]\ne1: (X, Y) -> Z\n", 5),
+        ] {
+            let err = gmml::parse_str(src).unwrap_err();
+            assert_eq!(err.location().unwrap().span.column, *column, "{}", src);
+        }
+    }
+}
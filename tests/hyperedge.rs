@@ -0,0 +1,121 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::symbols;
+    use gmml::{DocumentMut, EdgeKind, GValue};
+    use serde::Deserialize;
+
+    const SRC: &str = "[Model]
+X + Y + W -> Z : JointAttack()
+Z <- X + Y
+(A, B) -> C + D
+X -> Y
+";
+
+    #[test]
+    fn parse_hyperedges() {
+        let document = gmml::parse_str(SRC).unwrap();
+        let block = document.get("Model").unwrap();
+        let hyperedges: Vec<(Vec<GValue>, Vec<GValue>, EdgeKind)> = block
+            .hyperedges()
+            .map(|edge| (edge.sources.to_vec(), edge.targets.to_vec(), edge.kind))
+            .collect();
+        assert_eq!(
+            hyperedges,
+            vec![
+                (
+                    symbols(&["X", "Y", "W"]),
+                    symbols(&["Z"]),
+                    EdgeKind::Directed
+                ),
+                (symbols(&["X", "Y"]), symbols(&["Z"]), EdgeKind::Reverse),
+                (symbols(&["A"]), symbols(&["C", "D"]), EdgeKind::Directed),
+                (symbols(&["B"]), symbols(&["C", "D"]), EdgeKind::Directed),
+            ]
+        );
+        assert_eq!(block.edges().count(), 1);
+        let written: Vec<String> = block.values().map(|value| value.to_string()).collect();
+        assert_eq!(
+            written,
+            vec![
+                "X + Y + W -> Z : JointAttack()",
+                "Z <- X + Y",
+                "A -> C + D",
+                "B -> C + D",
+                "X -> Y",
+            ]
+        );
+        assert_eq!(
+            &SRC[block.entries[0].span.start..block.entries[0].span.end],
+            "X + Y + W -> Z : JointAttack()"
+        );
+
+        for src in &[
+            "[Model]\nX + Y\n",
+            "[Model]\nX + Y = 1\n",
+            "[Model]\nX + -> Z\n",
+        ] {
+            assert!(gmml::parse_str(src).is_err(), "{}", src);
+        }
+    }
+
+    #[test]
+    fn query_serde_and_writer() {
+        let document = gmml::parse_str(SRC).unwrap();
+        let matches = gmml::query(&document, "Model/edges[from=W]").unwrap();
+        assert_eq!(matches.len(), 1);
+        let matches = gmml::query(&document, "Model/edges[to=D]").unwrap();
+        assert_eq!(matches.len(), 2);
+
+        let src = gmml::to_string(&document).unwrap();
+        assert_eq!(gmml::parse_str(&src).unwrap(), document);
+        let copy = gmml::to_value(&document.get("Model").unwrap().entries[1].node).unwrap();
+        assert_eq!(copy, document.get("Model").unwrap().entries[1].node);
+
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Joint {
+            sources: Vec<String>,
+            targets: Vec<String>,
+        }
+        let value = &document.get("Model").unwrap().entries[1].node;
+        assert_eq!(
+            gmml::from_value::<Joint>(value).unwrap(),
+            Joint {
+                sources: vec!["X".to_string(), "Y".to_string()],
+                targets: vec!["Z".to_string()],
+            }
+        );
+
+        let binary = GValue::HyperEdge {
+            sources: symbols(&["X"]),
+            targets: symbols(&["Y"]),
+            kind: EdgeKind::Directed,
+        };
+        let mut document = DocumentMut::parse(SRC).unwrap();
+        assert!(document.push("Model", binary).is_err());
+    }
+
+    #[test]
+    fn join_layout() {
+        let expected = gmml::parse_str("[Model]\nX + Y -> Z\n").unwrap();
+        for src in &[
+            "[Model]\nX +Y -> Z\n",
+            "[Model]\nX+Y->Z\n",
+            "[Model]\nX +\nY -> Z\n",
+            "[Model]\nX + Y\n-> Z\n",
+            "[Model]\nX + Y ; sources\n-> Z\n",
+        ] {
+            assert_eq!(gmml::parse_str(src).unwrap(), expected, "{}", src);
+        }
+
+        let document = gmml::parse_str("[Model]\nX -> Y + Z -> W\n").unwrap();
+        let written: Vec<String> = document["Model"]
+            .values()
+            .map(|value| value.to_string())
+            .collect();
+        assert_eq!(written, vec!["X -> Y + Z", "Y + Z -> W"]);
+        let err = gmml::parse_str("[Model]\nX ++ Y -> Z\n").unwrap_err();
+        assert_eq!(err.location().unwrap().span.column, 4);
+    }
+}
@@ -0,0 +1,53 @@
+#[cfg(test)]
+mod tests {
+    use gmml::{Diagnostic, Error};
+
+    fn entries(src: &str) -> Vec<String> {
+        let root = gmml::parse_str(src).unwrap();
+        root["A"].values().map(|v| format!("{:?}", v)).collect()
+    }
+
+    #[test]
+    fn bool_and_null() {
+        assert_eq!(
+            entries("[A]\nvisible = true\nhidden = false\nparent = none\nX -> Y : (true, none)\n"),
+            vec![
+                "Pair(Symbol(\"visible\"), Bool(true))",
+                "Pair(Symbol(\"hidden\"), Bool(false))",
+                "Pair(Symbol(\"parent\"), Null)",
+                "Pair(Edge(Symbol(\"X\"), Symbol(\"Y\"), Directed), Vec([Bool(true), Null]))",
+            ]
+        );
+    }
+
+    #[test]
+    fn quoted_keywords() {
+        assert_eq!(
+            entries("[A]\n`true` -> `none`\nflag = `false`\n`node with space` = 1\n"),
+            vec![
+                "Edge(Symbol(\"true\"), Symbol(\"none\"), Directed)",
+                "Pair(Symbol(\"flag\"), Symbol(\"false\"))",
+                "Pair(Symbol(\"node with space\"), Integer(1))",
+            ]
+        );
+    }
+
+    #[test]
+    fn keywords_are_not_names() {
+        let src = "[A]\nX -> true\n";
+        let err = gmml::parse_str(src).unwrap_err();
+        assert!(matches!(&err, Error::ReservedKeyword { keyword, .. } if keyword == "true"));
+        let rendered = Diagnostic::from(&err).render(src, false);
+        assert!(
+            rendered.contains("help: quote it with backticks to use it as a name: `` `true` ``")
+        );
+        assert!(matches!(
+            gmml::parse_str("[A]\nnone = 1\n"),
+            Err(Error::ReservedKeyword { .. })
+        ));
+        assert!(matches!(
+            gmml::parse_str("[A]\nX = `open\n"),
+            Err(Error::UnterminatedIdentifier { .. })
+        ));
+    }
+}
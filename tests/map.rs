@@ -0,0 +1,87 @@
+#[cfg(test)]
+mod tests {
+    use gmml::{Diagnostic, Error, GValue, Map};
+
+    fn leaf(src: &str, name: &str) -> GValue {
+        let root = gmml::parse_str(src).unwrap();
+        let value = root["A"].values().find_map(|entry| match entry {
+            GValue::Pair(key, value) if **key == GValue::Symbol(name.to_string()) => {
+                Some((**value).clone())
+            }
+            _ => None,
+        });
+        value.unwrap()
+    }
+
+    #[test]
+    fn struct_and_list_are_distinct() {
+        let src = "[A]\nS = {has_class: Enemy}\nL = (has_class, Enemy)\nE = {}\n";
+        let map = match leaf(src, "S") {
+            GValue::Map(map) => map,
+            value => panic!("unexpected value: {:?}", value),
+        };
+        assert_eq!(
+            map.get("has_class"),
+            Some(&GValue::Symbol("Enemy".to_string()))
+        );
+        assert!(matches!(leaf(src, "L"), GValue::Vec(ref values) if values.len() == 2));
+        assert_eq!(leaf(src, "E"), GValue::Map(Map::new()));
+    }
+
+    #[test]
+    fn ordered_lookup() {
+        let src = "[A]\nS = {z: 1, \"key\": 2, 3: three, 1.5: x, \"a b\": 4}\n";
+        let map = match leaf(src, "S") {
+            GValue::Map(map) => map,
+            value => panic!("unexpected value: {:?}", value),
+        };
+        let keys: Vec<String> = map.keys().map(|key| key.to_string()).collect();
+        assert_eq!(keys, vec!["z", "\"key\"", "3", "1.5", "\"a b\""]);
+        assert_eq!(map.get("z"), Some(&GValue::Integer(1)));
+        assert_eq!(map.get("key"), Some(&GValue::Integer(2)));
+        assert_eq!(
+            map.get(&GValue::Symbol("key".to_string())),
+            Some(&GValue::Integer(2))
+        );
+        assert_eq!(map.get("a b"), Some(&GValue::Integer(4)));
+        assert_eq!(map.get(&3), Some(&GValue::Symbol("three".to_string())));
+        assert_eq!(map.get(&1.5), Some(&GValue::Symbol("x".to_string())));
+        assert_eq!(map.get("missing"), None);
+
+        let mut map = map;
+        assert_eq!(
+            map.insert(GValue::Symbol("z".to_string()), GValue::Null),
+            Some(GValue::Integer(1))
+        );
+        assert_eq!(map.iter().next().unwrap().1, GValue::Null);
+    }
+
+    #[test]
+    fn duplicate_keys() {
+        let src = "[A]\nS = {a: 1,\n     b: 2,\n     a: 3}\n";
+        let err = gmml::parse_str(src).unwrap_err();
+        match &err {
+            Error::DuplicateKey {
+                key,
+                first,
+                location,
+            } => {
+                assert_eq!(key, "a");
+                assert_eq!((first.line, first.column), (2, 6));
+                assert_eq!((location.span.line, location.span.column), (4, 6));
+            }
+            err => panic!("unexpected error: {:?}", err),
+        }
+        let rendered = Diagnostic::from(&err).render(src, false);
+        assert!(rendered.contains("first defined here"));
+        match gmml::parse_str("[A]\nS = {a: 1, \"a\": 2}\n") {
+            Err(Error::DuplicateKey { key, location, .. }) => {
+                assert_eq!(key, "\"a\"");
+                assert_eq!(location.span.column, 12);
+            }
+            other => panic!("expected a duplicate key, got {:?}", other),
+        }
+        assert!(gmml::parse_str("[A]\nS = {a.b: 1, \"a.b\": 2}\n").is_err());
+        assert!(gmml::parse_str("[A]\nS = {a: 1, \"a b\": 2, 1: 3}\n").is_ok());
+    }
+}
@@ -0,0 +1,96 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::message;
+    use gmml::{DocumentMut, Error};
+
+    const SRC: &str = "[Model]
+e1: Y -> Z : Attack()
+e2: Y -> Z : Defend()
+Y -> Z : Retreat()
+j1: X + Y -> Z
+";
+
+    #[test]
+    fn parallel_edges_by_id() {
+        let document = gmml::parse_str(SRC).unwrap();
+        let block = document.get("Model").unwrap();
+        let parallel: Vec<(Option<&str>, Option<&str>)> = block
+            .edges_between("Y", "Z")
+            .map(|edge| (edge.id, edge.message()))
+            .collect();
+        assert_eq!(
+            parallel,
+            vec![
+                (Some("e1"), Some("Attack")),
+                (Some("e2"), Some("Defend")),
+                (None, Some("Retreat")),
+            ]
+        );
+        assert_eq!(block.edge("e2").unwrap().stmt, Some(&message("Defend")));
+        assert!(block.edge("e3").is_none());
+        assert_eq!(block.hyperedge("j1").unwrap().sources.len(), 2);
+        assert_eq!(
+            block.values().next().unwrap().to_string(),
+            "e1: Y -> Z : Attack()"
+        );
+        assert_eq!(
+            &SRC[block.entries[1].span.start..block.entries[1].span.end],
+            "e2: Y -> Z : Defend()"
+        );
+
+        let matches = gmml::query(&document, "Model/e2").unwrap();
+        assert_eq!(matches.len(), 1);
+        let matches = gmml::query(&document, "Model/edges[id=e1]").unwrap();
+        assert_eq!(matches[0].to_string(), "e1: Y -> Z : Attack()");
+
+        let src = gmml::to_string(&document).unwrap();
+        assert_eq!(gmml::parse_str(&src).unwrap(), document);
+        assert_eq!(gmml::to_document(&document).unwrap(), document);
+    }
+
+    #[test]
+    fn invalid_ids() {
+        match gmml::parse_str("[Model]\ne1: X -> Y\ne1: Y -> Z\n") {
+            Err(Error::DuplicateEdgeId {
+                id,
+                first,
+                location,
+            }) => {
+                assert_eq!(id, "e1");
+                assert_eq!((first.line, location.span.line), (2, 3));
+            }
+            other => panic!("expected duplicate edge id, got {:?}", other),
+        }
+        assert!(gmml::parse_str("[A]\ne1: X -> Y\n[B]\ne1: X -> Y\n").is_ok());
+        for src in &[
+            "[Model]\ne1: X\n",
+            "[Model]\ne1: X -> Y -> Z\n",
+            "[Model]\ne1: (X, Y) -> Z\n",
+            "[Model]\n\"e1\": X -> Y\n",
+        ] {
+            assert!(gmml::parse_str(src).is_err(), "{}", src);
+        }
+        let (document, errors) =
+            gmml::parse_str_recovering("[Model]\ne1: X -> Y\ne1: Y -> Z\nZ -> X\n");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(document["Model"].len(), 3);
+    }
+
+    #[test]
+    fn half_typed_id() {
+        for src in &["[M]\nB:", "[M]\nB:\n[N]\n", "[M]\nB:\nX -> Y\n"] {
+            let error = gmml::parse_str(src).unwrap_err();
+            let span = error.location().unwrap().span;
+            assert_eq!((span.line, span.column), (2, 3), "{}", src);
+            assert!(gmml::format::format_str(src).is_err(), "{}", src);
+            assert!(DocumentMut::parse(src).is_err(), "{}", src);
+        }
+        let (document, errors) = gmml::parse_str_recovering("[M]\nB:\n[N]\nX\n");
+        assert_eq!(errors.len(), 1);
+        let names: Vec<&str> = document.blocks().map(|block| block.name.as_str()).collect();
+        assert_eq!(names, vec!["M", "N"]);
+        assert_eq!(document["N"].len(), 1);
+    }
+}
@@ -0,0 +1,131 @@
+#[cfg(test)]
+mod tests {
+    use gmml::{Error, GValue};
+
+    const SRC: &str = "[Model]
+Speed = 1
+Cost = 2
+
+[Model.Attack]
+Cost = 5
+X -> Y : Attack()
+
+[Model.Attack.Phase1]
+Y -> Z : Attack()
+
+[Model.Defense]
+Z -> X : Defend()
+
+[Exists]
+Z = X
+";
+
+    fn names<'a>(blocks: impl Iterator<Item = &'a gmml::Block>) -> Vec<&'a str> {
+        blocks.map(|block| block.name.as_str()).collect()
+    }
+
+    #[test]
+    fn block_tree() {
+        let document = gmml::parse_str(SRC).unwrap();
+        assert_eq!(names(document.roots()), vec!["Model", "Exists"]);
+        assert_eq!(
+            names(document.children("Model")),
+            vec!["Model.Attack", "Model.Defense"]
+        );
+        assert_eq!(
+            names(document.ancestors("Model.Attack.Phase1")),
+            vec!["Model.Attack", "Model"]
+        );
+        let block = document.get("Model.Attack.Phase1").unwrap();
+        assert_eq!(block.parent(), Some("Model.Attack"));
+        assert_eq!(
+            block.path().collect::<Vec<_>>(),
+            vec!["Model", "Attack", "Phase1"]
+        );
+        assert_eq!(
+            &SRC[block.name_span.start..block.name_span.end],
+            "Model.Attack.Phase1"
+        );
+        let src = gmml::to_string(&document).unwrap();
+        assert_eq!(gmml::parse_str(&src).unwrap(), document);
+
+        for src in &[
+            "[Model.]\n",
+            "[Model .Attack]\n",
+            "[.Attack]\n",
+            "[Model.Attack]\n[Model.Attack]\n",
+        ] {
+            assert!(gmml::parse_str(src).is_err(), "{}", src);
+        }
+    }
+
+    #[test]
+    fn quoted_names_can_not_nest() {
+        match gmml::parse_str("[`Model.Attack`]\nX\n") {
+            Err(Error::UnexpectedToken { location, .. }) => assert_eq!(location.span.column, 2),
+            other => panic!("expected an error, got {:?}", other),
+        }
+        assert!(gmml::parse_str("[Model.`Attack.1`]\n").is_err());
+        let document = gmml::parse_str("[`Model`.`Attack 1`]\nX\n").unwrap();
+        assert_eq!(names(document.roots()), vec!["Model.Attack 1"]);
+        assert_eq!(
+            gmml::to_string(&document).unwrap(),
+            "[Model.`Attack 1`]\nX\n"
+        );
+    }
+
+    #[test]
+    fn missing_levels_and_pushed_blocks() {
+        let mut document = gmml::parse_str("[A.B.C]\nX\n[A]\nY\n").unwrap();
+        assert_eq!(names(document.roots()), vec!["A"]);
+        assert_eq!(names(document.children("A")), vec!["A.B.C"]);
+        assert_eq!(names(document.children("A.B")), Vec::<&str>::new());
+        assert_eq!(names(document.ancestors("A.B.C")), vec!["A"]);
+        assert_eq!(names(document.ancestors("A.B")), vec!["A"]);
+
+        document.push(gmml::Block::new("A.B"));
+        assert_eq!(names(document.children("A")), vec!["A.B"]);
+        assert_eq!(names(document.ancestors("A.B.C")), vec!["A.B", "A"]);
+        document.get_mut("A").unwrap().name = "Z".to_string();
+        assert_eq!(names(document.roots()), vec!["Z", "A.B"]);
+        assert_eq!(names(document.ancestors("A.B.C")), vec!["A.B"]);
+    }
+
+    #[test]
+    fn inherited_defaults() {
+        let document = gmml::parse_str(SRC).unwrap();
+        let lookup = |block: &str, key: &str| document.get_inherited(block, key).ok().cloned();
+        assert_eq!(
+            lookup("Model.Attack.Phase1", "Cost"),
+            Some(GValue::Integer(5))
+        );
+        assert_eq!(
+            lookup("Model.Attack.Phase1", "Speed"),
+            Some(GValue::Integer(1))
+        );
+        assert_eq!(lookup("Model.Defense", "Cost"), Some(GValue::Integer(2)));
+        assert_eq!(lookup("Exists", "Speed"), None);
+        assert!(document["Model.Attack"].get("Speed").is_err());
+
+        let defs: Vec<String> = document
+            .inherited_defs("Model.Attack")
+            .into_iter()
+            .map(|(key, value)| format!("{} = {}", key, value))
+            .collect();
+        assert_eq!(defs, vec!["Cost = 5", "Speed = 1"]);
+
+        let matches = gmml::query(&document, "Model.Attack/edges").unwrap();
+        assert_eq!(matches.len(), 1);
+        let matches = gmml::query(&document, "Model.Attack.Phase1/edges[to=Z]").unwrap();
+        assert_eq!(matches.len(), 1);
+        let matches = gmml::query(&document, "Model/Cost").unwrap();
+        assert_eq!(matches, vec![&GValue::Integer(2)]);
+        let matches = gmml::query(&document, "Model.Attack.Cost").unwrap();
+        assert_eq!(matches, vec![&GValue::Integer(5)]);
+
+        let document = gmml::parse_str("[A]\nx = 1\n[A.B]\n\"x\" = 2\n").unwrap();
+        let defs = document.inherited_defs("A.B");
+        assert_eq!(defs.len(), 1);
+        assert_eq!(defs[0].1, &GValue::Integer(2));
+    }
+}
@@ -0,0 +1,81 @@
+#[cfg(test)]
+mod tests {
+    use gmml::parse::{Scanner, Token};
+    use gmml::Error;
+
+    fn lex_one(src: &str) -> Result<Token, Error> {
+        let mut toks = Scanner::from_str(src).tokenize()?;
+        assert_eq!(toks.len(), 1, "{}", src);
+        Ok(toks.remove(0).node)
+    }
+
+    #[test]
+    fn integers() {
+        assert_eq!(lex_one("42").unwrap(), Token::Integer(42));
+        assert_eq!(lex_one("-42").unwrap(), Token::Integer(-42));
+        assert_eq!(lex_one("1_000_000").unwrap(), Token::Integer(1_000_000));
+        assert_eq!(lex_one("0xFF_ff").unwrap(), Token::Integer(0xffff));
+        assert_eq!(lex_one("-0x10").unwrap(), Token::Integer(-16));
+        assert_eq!(lex_one("0o755").unwrap(), Token::Integer(0o755));
+        assert_eq!(lex_one("0b1010_1010").unwrap(), Token::Integer(0b1010_1010));
+        assert_eq!(
+            lex_one("9223372036854775807").unwrap(),
+            Token::Integer(i64::MAX)
+        );
+        assert_eq!(
+            lex_one("-9223372036854775808").unwrap(),
+            Token::Integer(i64::MIN)
+        );
+    }
+
+    #[test]
+    fn floats() {
+        assert_eq!(lex_one("123.456").unwrap(), Token::Float(123.456));
+        assert_eq!(lex_one("-0.5").unwrap(), Token::Float(-0.5));
+        assert_eq!(lex_one("1e-6").unwrap(), Token::Float(1e-6));
+        assert_eq!(lex_one("2.5E+3").unwrap(), Token::Float(2500.0));
+        assert_eq!(lex_one("1_000.000_1").unwrap(), Token::Float(1000.0001));
+        assert_eq!(lex_one("1e3").unwrap(), Token::Float(1000.0));
+    }
+
+    #[test]
+    fn invalid_numbers() {
+        for src in &[
+            "1.2.3", "1.", "1_", "1__", "1e", "1e+", "0x", "0xG", "0b102", "12ab", "1._5",
+        ] {
+            assert!(
+                matches!(lex_one(src), Err(Error::InvalidNumber { .. })),
+                "{}",
+                src
+            );
+        }
+        for src in &[
+            "9223372036854775808",
+            "-9223372036854775809",
+            "0x1_0000_0000_0000_0000",
+            "1e999",
+        ] {
+            assert!(
+                matches!(lex_one(src), Err(Error::NumberOverflow { .. })),
+                "{}",
+                src
+            );
+        }
+    }
+
+    #[test]
+    fn numbers_in_models() {
+        let root =
+            gmml::parse_str("[A]\nweight = -3\np = 1e-6\nid = 0xdead_beef\n1->-2:3\n").unwrap();
+        let values: Vec<String> = root["A"].values().map(|v| format!("{:?}", v)).collect();
+        assert_eq!(
+            values,
+            vec![
+                "Pair(Symbol(\"weight\"), Integer(-3))",
+                "Pair(Symbol(\"p\"), Float(1e-6))",
+                "Pair(Symbol(\"id\"), Integer(3735928559))",
+                "Pair(Edge(Integer(1), Integer(-2), Directed), Integer(3))",
+            ]
+        );
+    }
+}
@@ -0,0 +1,107 @@
+#[cfg(test)]
+mod tests {
+    use gmml::{DocumentMut, EdgeKind, GValue};
+
+    const SRC: &str = "[Model]
+Router.eth0 -> Switch.p1 : Link()
+Router.eth1 = Z.has_class
+Z -> Y
+";
+
+    fn path(names: &[&str]) -> GValue {
+        GValue::Path(names.iter().map(|name| name.to_string()).collect())
+    }
+
+    #[test]
+    fn parse_paths() {
+        let document = gmml::parse_str(SRC).unwrap();
+        let block = document.get("Model").unwrap();
+        let edge = block.edges().next().unwrap();
+        assert_eq!(edge.from, &path(&["Router", "eth0"]));
+        assert_eq!(edge.to, &path(&["Switch", "p1"]));
+        assert_eq!(
+            block.get("Router.eth1").unwrap(),
+            &path(&["Z", "has_class"])
+        );
+        let written: Vec<String> = block.values().map(|value| value.to_string()).collect();
+        assert_eq!(
+            written,
+            vec![
+                "Router.eth0 -> Switch.p1 : Link()",
+                "Router.eth1 = Z.has_class",
+                "Z -> Y",
+            ]
+        );
+        assert_eq!(
+            &SRC[block.entries[0].span.start..block.entries[0].span.end],
+            "Router.eth0 -> Switch.p1 : Link()"
+        );
+
+        for src in &[
+            "[Model]\nRouter. eth0 -> Switch\n",
+            "[Model]\nRouter .eth0 -> Switch\n",
+            "[Model]\nRouter.5 -> Switch\n",
+            "[Model]\nRouter. -> Switch\n",
+        ] {
+            assert!(gmml::parse_str(src).is_err(), "{}", src);
+        }
+    }
+
+    #[test]
+    fn query_and_serde() {
+        let document = gmml::parse_str(SRC).unwrap();
+        let matches = gmml::query(&document, "Model/edges[from=\"Router.eth0\"]").unwrap();
+        assert_eq!(matches.len(), 1);
+        let matches = gmml::query(&document, "Model/edges[to=Switch]").unwrap();
+        assert!(matches.is_empty());
+
+        let src = gmml::to_string(&document).unwrap();
+        assert_eq!(gmml::parse_str(&src).unwrap(), document);
+        assert_eq!(gmml::to_document(&document).unwrap(), document);
+        let value = path(&["Z", "has_class"]);
+        assert_eq!(gmml::to_value(&value).unwrap(), value);
+        assert_eq!(gmml::from_value::<String>(&value).unwrap(), "Z.has_class");
+
+        let edge = GValue::Edge(
+            Box::new(path(&["Z", ""])),
+            Box::new(path(&["Y"])),
+            EdgeKind::Directed,
+        );
+        let mut document = DocumentMut::parse(SRC).unwrap();
+        assert!(document.push("Model", edge).is_err());
+    }
+
+    #[test]
+    fn paths_in_every_position() {
+        let src = "[Model]
+A.b.c -> `a b`.c
+(A.p, B.q) -> C
+A.p + B.q -> C.r
+e1: A.p -> B.q
+X = Attack(Z.hp, 2)
+Y = {a.b: 1}
+X.y ; note
+";
+        let document = gmml::parse_str(src).unwrap();
+        let block = document.get("Model").unwrap();
+        let edge = block.edges().next().unwrap();
+        assert_eq!(edge.from, &path(&["A", "b", "c"]));
+        assert_eq!(edge.to, &path(&["a b", "c"]));
+        assert_eq!(block.edge("e1").unwrap().to, &path(&["B", "q"]));
+        let written: Vec<String> = block.values().map(|value| value.to_string()).collect();
+        assert_eq!(
+            written,
+            vec![
+                "A.b.c -> `a b`.c",
+                "A.p -> C",
+                "B.q -> C",
+                "A.p + B.q -> C.r",
+                "e1: A.p -> B.q",
+                "X = Attack(Z.hp, 2)",
+                "Y = {a.b: 1}",
+                "X.y",
+            ]
+        );
+        assert!(gmml::parse_str("[Model]\n\"A\".p -> B\n").is_err());
+    }
+}
@@ -0,0 +1,94 @@
+#[cfg(test)]
+mod tests {
+    use gmml::{Error, GValue, Query};
+
+    const SRC: &str = "[Exists]
+X
+Y
+Z = {has_class: Enemy, hp: 3}
+
+[Env]
+X -> Y : Positive
+Y -> Z : Negative
+
+[Model]
+Y -> Z : Attack()
+Z -> Y : Attack()
+X -> Z : Defend(1)
+Items = (a, (b, c))
+";
+
+    fn run(query: &str) -> Vec<String> {
+        let document = gmml::parse_str(SRC).unwrap();
+        gmml::query(&document, query)
+            .unwrap()
+            .into_iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn edges_by_message() {
+        assert_eq!(
+            run("Model/edges[msg=Attack]"),
+            vec!["Y -> Z : Attack()", "Z -> Y : Attack()"]
+        );
+        assert_eq!(run("Model/edges[from=Y][to=Z]"), vec!["Y -> Z : Attack()"]);
+        assert_eq!(run("Env/edges[msg!=Positive]"), vec!["Y -> Z : Negative"]);
+        assert_eq!(run("*/edges[to=Y]").len(), 2);
+        assert_eq!(run("Model/edges[2]"), vec!["X -> Z : Defend(1)"]);
+        assert!(run("Model/edges[3]").is_empty());
+    }
+
+    #[test]
+    fn definitions_and_keys() {
+        assert_eq!(run("Exists/Z.has_class"), vec!["Enemy"]);
+        assert_eq!(run("Exists/Z/*"), vec!["Enemy", "3"]);
+        assert_eq!(run("Exists/Z[hp=3]"), vec!["{has_class: Enemy, hp: 3}"]);
+        assert_eq!(run("*/defs[key=Z]"), vec!["Z = {has_class: Enemy, hp: 3}"]);
+        assert_eq!(run("Exists/*[1]"), vec!["Y"]);
+        assert_eq!(run("Exists/X"), vec!["X"]);
+        assert_eq!(run("Model/Items/*[1]/c"), vec!["c"]);
+        assert!(run("Missing/*").is_empty());
+    }
+
+    #[test]
+    fn select_value() {
+        let value = GValue::Vec(vec![GValue::Integer(1), GValue::Symbol("a".into())]);
+        let query = Query::parse("*[1]").unwrap();
+        assert_eq!(
+            query.select_value(&value),
+            vec![&GValue::Symbol("a".into())]
+        );
+    }
+
+    #[test]
+    fn nested_block_over_definition() {
+        let src = "[Model]\nAttack = {Cost: 1}\n\n[Model.Attack]\nCost = 5\nX -> Y\n";
+        let document = gmml::parse_str(src).unwrap();
+        let matches = gmml::query(&document, "Model.Attack/Cost").unwrap();
+        assert_eq!(matches, vec![&GValue::Integer(5)]);
+        let matches = gmml::query(&document, "Model.Attack/*").unwrap();
+        assert_eq!(matches.len(), 2);
+
+        let document = gmml::parse_str("[Model]\nAttack = {Cost: 1}\n").unwrap();
+        let matches = gmml::query(&document, "Model.Attack.Cost").unwrap();
+        assert_eq!(matches, vec![&GValue::Integer(1)]);
+    }
+
+    #[test]
+    fn invalid_query() {
+        for (query, column) in &[
+            ("Model/edges[0", 14),
+            ("Model/", 7),
+            ("Model/edges[msg]", 16),
+            ("  Model/", 9),
+            ("\tModel/edges[0  ", 15),
+        ] {
+            match Query::parse(query) {
+                Err(Error::InvalidQuery { column: found, .. }) => assert_eq!(found, *column),
+                other => panic!("{:?}", other),
+            }
+        }
+    }
+}
@@ -0,0 +1,50 @@
+#[cfg(test)]
+mod tests {
+    use gmml::parse::{Parser, Scanner, AST};
+
+    const SRC: &str = "[A]
+X = 1 2
+Y = ok
+Z = $
+[B
+Q = 1
+[C]
+W -> : 3
+V = (1, 2)
+";
+
+    #[test]
+    fn reports_every_error() {
+        let (root, errors) = gmml::parse_str_recovering(SRC);
+        let lines: Vec<usize> = errors.iter().map(|err| err.span().unwrap().line).collect();
+        assert_eq!(lines, vec![2, 4, 5, 8]);
+        assert_eq!(root["A"].len(), 1);
+        assert!(!root.contains("B"));
+        assert_eq!(root["C"].len(), 1);
+    }
+
+    #[test]
+    fn partial_ast_has_error_nodes() {
+        let (toks, lex_errors) = Scanner::from_str(SRC).tokenize_recovering();
+        assert_eq!(lex_errors.len(), 1);
+        let (blocks, errors) = Parser::new(toks).parse_ast_recovering();
+        assert_eq!(errors.len(), 3);
+        assert_eq!(blocks.len(), 3);
+        assert!(matches!(blocks[1].node, AST::Error));
+        match &blocks[0].node {
+            AST::Block { content, .. } => {
+                assert!(matches!(content[0].node, AST::Error));
+                assert!(matches!(content[1].node, AST::LeafDef { .. }));
+                assert!(matches!(content[2].node, AST::Error));
+            }
+            node => panic!("unexpected node: {:?}", node),
+        }
+    }
+
+    #[test]
+    fn valid_input_has_no_errors() {
+        let (root, errors) = gmml::parse_str_recovering("[A]\nX -> Y : Attack()\n");
+        assert!(errors.is_empty());
+        assert!(root.contains("A"));
+    }
+}
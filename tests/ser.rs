@@ -0,0 +1,216 @@
+#[cfg(test)]
+mod tests {
+    use gmml::{Document, Error, GValue};
+    use serde::{Deserialize, Serialize};
+    use std::collections::BTreeMap;
+    use std::fs;
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    enum Action {
+        Attack(),
+        Heal(u32),
+        Move(i64, i64),
+        Say { text: String },
+        Wait,
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Unit {
+        has_class: String,
+        hp: Option<u32>,
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Model {
+        #[serde(rename = "Exists")]
+        exists: BTreeMap<String, Option<Unit>>,
+        #[serde(rename = "Env")]
+        env: BTreeMap<(String, String), String>,
+        #[serde(rename = "Model")]
+        model: Vec<((String, String), Action)>,
+        #[serde(rename = "Nodes")]
+        nodes: Vec<(String, String)>,
+    }
+
+    fn model() -> Model {
+        let mut exists = BTreeMap::new();
+        exists.insert("X".to_string(), None);
+        exists.insert(
+            "文字列".to_string(),
+            Some(Unit {
+                has_class: "Enemy".to_string(),
+                hp: Some(3),
+            }),
+        );
+        exists.insert(
+            "two words".to_string(),
+            Some(Unit {
+                has_class: "line\nbreak \"quoted\"".to_string(),
+                hp: None,
+            }),
+        );
+        let mut env = BTreeMap::new();
+        env.insert(("X".to_string(), "Y".to_string()), "Positive".to_string());
+        let edge = |from: &str, to: &str| (from.to_string(), to.to_string());
+        Model {
+            exists,
+            env,
+            model: vec![
+                (edge("Y", "Z"), Action::Attack()),
+                (edge("Z", "Y"), Action::Heal(2)),
+                (edge("X", "Z"), Action::Move(1, -1)),
+                (
+                    edge("X", "true"),
+                    Action::Say {
+                        text: "hi".to_string(),
+                    },
+                ),
+                (edge("1", "Y"), Action::Wait),
+            ],
+            nodes: vec![edge("A", "B")],
+        }
+    }
+
+    #[test]
+    fn write_model() {
+        let src = gmml::to_string(&model()).unwrap();
+        assert_eq!(
+            src,
+            "[Exists]
+X
+\"two words\" = {has_class: \"line\\nbreak \\\"quoted\\\"\", hp: none}
+\"文字列\" = {has_class: \"Enemy\", hp: 3}
+
+[Env]
+X -> Y : \"Positive\"
+
+[Model]
+Y -> Z : Attack()
+Z -> Y : Heal(2)
+X -> Z : Move(1, -1)
+X -> \"true\" : Say({text: \"hi\"})
+\"1\" -> Y : Wait
+
+[Nodes]
+A -> B
+"
+        );
+        assert_eq!(gmml::from_str::<Model>(&src).unwrap(), model());
+    }
+
+    #[test]
+    fn documents_round_trip() {
+        for path in &[
+            "example/group_attack.gmml",
+            "example/group_edges.gmml",
+            "example/test1.gmml",
+            "example/test2.gmml",
+        ] {
+            let document = gmml::parse_file(path).unwrap();
+            let src = gmml::to_string(&document).unwrap();
+            assert_eq!(gmml::parse_str(&src).unwrap(), document, "{}", src);
+        }
+        let src = fs::read_to_string("example/test2.gmml").unwrap();
+        let document = gmml::parse_str(&src).unwrap();
+        assert_eq!(gmml::to_document(&document).unwrap(), document);
+    }
+
+    #[test]
+    fn scalar_and_optional_fields() {
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Settings {
+            depth: u32,
+            name: String,
+            mode: Option<Action>,
+            limit: Option<u32>,
+            seed: Option<u32>,
+        }
+        let settings = Settings {
+            depth: 3,
+            name: "search".to_string(),
+            mode: Some(Action::Wait),
+            limit: None,
+            seed: Some(7),
+        };
+        let src = gmml::to_string(&settings).unwrap();
+        assert_eq!(
+            src,
+            "[depth]\n3\n\n[name]\n\"search\"\n\n[mode]\nWait\n\n[limit]\n\n[seed]\n7\n"
+        );
+        assert_eq!(gmml::from_str::<Settings>(&src).unwrap(), settings);
+
+        let err = gmml::from_str::<Settings>(&src.replace("3\n", "-3\n")).unwrap_err();
+        assert_eq!(err.location().unwrap().span.line, 2);
+    }
+
+    #[test]
+    fn fields_written_as_values() {
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Plan {
+            enabled: bool,
+            first: Action,
+            then: Action,
+            units: Vec<Unit>,
+            costs: Vec<(String, i64)>,
+            links: Vec<(String, String)>,
+        }
+        let plan = Plan {
+            enabled: true,
+            first: Action::Heal(2),
+            then: Action::Move(1, -1),
+            units: vec![
+                Unit {
+                    has_class: "Enemy".to_string(),
+                    hp: Some(3),
+                },
+                Unit {
+                    has_class: "Ally".to_string(),
+                    hp: None,
+                },
+            ],
+            costs: vec![("move".to_string(), 2)],
+            links: vec![("X".to_string(), "Y".to_string())],
+        };
+        let src = gmml::to_string(&plan).unwrap();
+        assert_eq!(
+            src,
+            "[enabled]
+value = true
+
+[first]
+value = Heal(2)
+
+[then]
+value = Move(1, -1)
+
+[units]
+value = ({has_class: \"Enemy\", hp: 3}, {has_class: \"Ally\", hp: none})
+
+[costs]
+move = 2
+
+[links]
+X -> Y
+"
+        );
+        assert_eq!(gmml::from_str::<Plan>(&src).unwrap(), plan);
+    }
+
+    #[test]
+    fn unwritable_values() {
+        let mut document = Document::new();
+        let mut block = gmml::Block::new("A");
+        block.push(GValue::Vec(vec![GValue::Integer(1)]));
+        document.push(block);
+        match gmml::to_string(&document) {
+            Err(Error::Serialize { message }) => {
+                assert_eq!(message, "list can not be written as a key")
+            }
+            other => panic!("{:?}", other),
+        }
+        let mut values = BTreeMap::new();
+        values.insert("A", BTreeMap::from([("x", f64::NAN)]));
+        assert!(gmml::to_string(&values).is_err());
+        assert!(gmml::to_string(&vec![1]).is_err());
+    }
+}
@@ -0,0 +1,67 @@
+#[cfg(test)]
+mod tests {
+    use gmml::parse::{GValue, Parser, Scanner, Token, AST};
+    use gmml::{Span, Spanned};
+
+    #[test]
+    fn token_spans_with_crlf() {
+        let toks = Scanner::from_str("[A]\r\nX -> 文字\r\n")
+            .tokenize()
+            .unwrap();
+        let spans: Vec<(Token, Span)> = toks.into_iter().map(|tok| (tok.node, tok.span)).collect();
+        assert_eq!(spans[3], (Token::Newline, Span::new(3, 5, 1, 4)));
+        assert_eq!(
+            spans[4],
+            (Token::Identifier("X".to_string()), Span::new(5, 6, 2, 1))
+        );
+        assert_eq!(
+            spans[8],
+            (
+                Token::Identifier("文字".to_string()),
+                Span::new(10, 16, 2, 6)
+            )
+        );
+        assert_eq!(spans[9], (Token::Newline, Span::new(16, 18, 2, 8)));
+    }
+
+    #[test]
+    fn crlf_matches_lf() {
+        let src = "[A]\nHello = 123.456\nX -> Y : Attack()\n";
+        let lf = gmml::parse_str(src).unwrap();
+        let crlf = gmml::parse_str(&src.replace('\n', "\r\n")).unwrap();
+        assert_eq!(lf, crlf);
+    }
+
+    #[test]
+    fn ast_spans() {
+        let src = "[Model]\nY -> Z : Attack(x)\nW = {a: 1,\n     b: 2}\n";
+        let toks = Scanner::from_str(src).tokenize().unwrap();
+        let blocks = Parser::new(toks).parse_ast().unwrap();
+        let (name, content) = match &blocks[0].node {
+            AST::Block { name, content } => (name, content),
+            _ => panic!("expected block"),
+        };
+        assert_eq!(&src[name.span.start..name.span.end], "Model");
+        assert_eq!(blocks[0].span.start, 0);
+        assert_eq!(blocks[0].span.end, src.len() - 1);
+        assert_eq!(
+            &src[content[0].span.start..content[0].span.end],
+            "Y -> Z : Attack(x)"
+        );
+        match &content[0].node {
+            AST::EdgeDef { target, stmt } => {
+                assert_eq!(target.span, Span::new(8, 14, 2, 1));
+                assert_eq!(stmt.span, Span::new(17, 26, 2, 10));
+            }
+            _ => panic!("expected edge definition"),
+        }
+        assert_eq!(content[1].span.line, 3);
+        assert_eq!(
+            &src[content[1].span.start..content[1].span.end],
+            "W = {a: 1,\n     b: 2}"
+        );
+
+        let value = Spanned::<GValue>::from(content[1].clone());
+        assert_eq!(value.span, content[1].span);
+    }
+}
@@ -0,0 +1,80 @@
+#[cfg(test)]
+mod tests {
+    use gmml::parse::{Scanner, Token};
+    use gmml::Error;
+
+    fn lex(src: &str) -> Vec<Token> {
+        Scanner::from_str(src)
+            .tokenize()
+            .unwrap()
+            .into_iter()
+            .map(|tok| tok.node)
+            .collect()
+    }
+
+    fn string(s: &str) -> Token {
+        Token::String(s.to_string())
+    }
+
+    #[test]
+    fn escapes() {
+        assert_eq!(
+            lex(r#""say \"hi\"\\\n\t\u{48}\u{1F600}""#),
+            vec![string("say \"hi\"\\\n\tH😀")]
+        );
+    }
+
+    #[test]
+    fn raw_strings() {
+        assert_eq!(lex(r#"r"C:\path\[a-z]+""#), vec![string(r"C:\path\[a-z]+")]);
+        assert_eq!(
+            lex(r###"r#"quote " inside"#"###),
+            vec![string("quote \" inside")]
+        );
+        assert_eq!(lex("raw"), vec![Token::Identifier("raw".to_string())]);
+    }
+
+    #[test]
+    fn multiline_strings() {
+        let src = "[A]\r\nX = \"\"\"\r\nfirst \"line\"\r\n\tsecond\\u{21}\"\"\"\r\nY = 1\r\n";
+        let toks = lex(src);
+        assert_eq!(toks[8], string("first \"line\"\n\tsecond!"));
+        assert!(gmml::parse_str(src).is_ok());
+    }
+
+    #[test]
+    fn invalid_escapes() {
+        let err = gmml::parse_str("[A]\nX = \"a\\qb\"\n").unwrap_err();
+        match err {
+            Error::InvalidEscape { sequence, location } => {
+                assert_eq!(sequence, "\\q");
+                assert_eq!((location.span.start, location.span.end), (10, 12));
+                assert_eq!(location.span.column, 7);
+            }
+            err => panic!("unexpected error: {:?}", err),
+        }
+        for src in &[
+            "\"\\u{D800}\"",
+            "\"\\u{}\"",
+            "\"\\u{1234567}\"",
+            "\"\\u41\"",
+        ] {
+            let err = Scanner::from_str(src).tokenize().unwrap_err();
+            assert!(matches!(err, Error::InvalidEscape { .. }), "{}", src);
+        }
+    }
+
+    #[test]
+    fn unterminated_strings() {
+        let err = gmml::parse_str("[A]\nX = \"open\nY = 1\n").unwrap_err();
+        match err {
+            Error::UnterminatedString { location } => {
+                assert_eq!((location.span.start, location.span.end), (8, 13));
+            }
+            err => panic!("unexpected error: {:?}", err),
+        }
+        for src in &["\"\"\"never closed\n", "r#\"never closed\"", "\"\\"] {
+            assert!(Scanner::from_str(src).tokenize().is_err(), "{}", src);
+        }
+    }
+}